@@ -0,0 +1,100 @@
+use std::path::Path;
+use std::time::Duration;
+
+/// The shortest `poll_interval` allowed, so a tiny one doesn't have the
+/// polling watcher scanning flat out
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Settings read from `.fragment/config.toml` in the notes root. Only flat
+/// `key = "value"` lines are understood; anything missing keeps its default.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    /// Folder under the root that archived notes are moved into
+    pub archive_folder: String,
+    /// Command line to open notes with, e.g. `nvim +{line} {path}`. See
+    /// `editor::open` for the placeholders. Unset means the OS default app.
+    pub editor: Option<String>,
+    /// Extension given to new notes, which picks their format: `md`, `org`
+    /// or `txt`
+    pub note_extension: String,
+    /// How changes to the notes are picked up
+    pub watcher: WatchMode,
+    /// How often the polling watcher scans the notes, and how often a
+    /// broken watcher is retried
+    pub poll_interval: Duration,
+}
+
+/// Where live updates come from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WatchMode {
+    /// The OS's file events, falling back to polling when they can't be had
+    Native,
+    /// Scanning file modified times and sizes every `poll_interval`, for
+    /// network and FUSE mounts that don't send events
+    Poll,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            archive_folder: "archive".to_string(),
+            editor: None,
+            note_extension: "md".to_string(),
+            watcher: WatchMode::Native,
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(root: &str) -> Config {
+        let path = Path::new(root).join(".fragment").join("config.toml");
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => Config::parse(&contents),
+            Err(_) => Config::default(),
+        }
+    }
+
+    fn parse(contents: &str) -> Config {
+        let mut config = Config::default();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let equals = match line.find('=') {
+                Some(equals) => equals,
+                None => continue,
+            };
+            let key = line[..equals].trim();
+            let value = line[equals + 1..]
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string();
+
+            match key {
+                "archive_folder" => config.archive_folder = value,
+                "editor" => config.editor = Some(value),
+                "note_extension" => {
+                    config.note_extension = value.trim_start_matches('.').to_string()
+                }
+                "watcher" => match value.as_str() {
+                    "native" => config.watcher = WatchMode::Native,
+                    "poll" => config.watcher = WatchMode::Poll,
+                    _ => eprintln!("Unknown watcher '{}', expected native or poll", value),
+                },
+                "poll_interval" => match value.parse::<f64>() {
+                    Ok(seconds) if seconds > 0.0 => {
+                        config.poll_interval =
+                            Duration::from_millis((seconds * 1000.0) as u64).max(MIN_POLL_INTERVAL)
+                    }
+                    _ => eprintln!("poll_interval should be a number of seconds: {}", value),
+                },
+                _ => eprintln!("Unknown config key: {}", key),
+            }
+        }
+
+        config
+    }
+}
@@ -0,0 +1,52 @@
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// Opens `path` in the editor. `template` is a command line such as
+/// `nvim +{line} {path}` or `code -g {path}:{line}:{col}`, where `{path}`,
+/// `{line}` and `{col}` are filled in for the note and the position to jump
+/// to. Without a template the note opens in the OS default app for its type.
+pub fn open(template: Option<&str>, path: &Path, line: u64, col: u64) -> io::Result<()> {
+    let template = match template {
+        Some(template) if !template.trim().is_empty() => template,
+        _ => return open::that(path).map(|_| ()),
+    };
+
+    let path = path.display().to_string();
+    let mut args = split_args(template).into_iter().map(|arg| {
+        arg.replace("{path}", &path)
+            .replace("{line}", &line.to_string())
+            .replace("{col}", &col.to_string())
+    });
+
+    let program = args
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Editor command is empty"))?;
+    Command::new(program).args(args).spawn().map(|_| ())
+}
+
+/// Splits a command line on whitespace, keeping quoted arguments together so
+/// paths with spaces can be used as the program.
+pub fn split_args(command: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut quote = None;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, c) if c.is_whitespace() => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            (_, c) => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+
+    args
+}
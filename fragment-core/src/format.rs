@@ -0,0 +1,171 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::frontmatter::{self, FrontMatter};
+use crate::links;
+use crate::markdown::{self, Block, Span, Style};
+use crate::org;
+use crate::rename::{self, Renaming};
+use crate::tags;
+
+/// Everything that depends on how a note is written. Indexing, titles and
+/// the preview go through this so each format is handled the same way.
+pub trait NoteFormat: Sync {
+    /// Splits the note's metadata from the rest of it
+    fn split_metadata<'a>(&self, contents: &'a str) -> (FrontMatter, &'a str);
+
+    /// The text of the first heading, for `TitleStrategy::Heading`
+    fn heading<'a>(&self, body: &'a str) -> Option<&'a str>;
+
+    fn tags(&self, front_matter: &FrontMatter, body: &str) -> Vec<Arc<str>>;
+
+    /// Names of the notes this note links to
+    fn links(&self, body: &str) -> Vec<Arc<str>>;
+
+    /// Points the links in `contents`, the note at `referrer`, that are to
+    /// a note being renamed at its new name. Every kind of link `links`
+    /// finds has to be handled, or renames would leave them broken.
+    fn rewrite_links(&self, contents: &str, referrer: &Path, renaming: &Renaming) -> String;
+
+    fn render(&self, body: &str) -> Vec<Block>;
+
+    /// A metadata block setting the note's title, if the format has one
+    fn title_metadata(&self, title: &str) -> Option<String>;
+
+    /// The first line of a new note headed `title`
+    fn heading_line(&self, title: &str) -> String;
+}
+
+pub struct Markdown;
+pub struct Org;
+pub struct PlainText;
+
+/// The format of the note at `path`, by its extension. Anything that isn't
+/// org or plain text is read as markdown.
+pub fn for_path(path: &Path) -> &'static dyn NoteFormat {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("org") => &Org,
+        Some("txt") | Some("text") => &PlainText,
+        _ => &Markdown,
+    }
+}
+
+impl NoteFormat for Markdown {
+    fn split_metadata<'a>(&self, contents: &'a str) -> (FrontMatter, &'a str) {
+        frontmatter::parse(contents)
+    }
+
+    fn heading<'a>(&self, body: &'a str) -> Option<&'a str> {
+        body.lines()
+            .find(|line| line.starts_with("# "))
+            .map(|line| line[2..].trim())
+    }
+
+    fn tags(&self, front_matter: &FrontMatter, body: &str) -> Vec<Arc<str>> {
+        tags::extract(front_matter, body)
+    }
+
+    fn links(&self, body: &str) -> Vec<Arc<str>> {
+        links::extract(body)
+    }
+
+    fn rewrite_links(&self, contents: &str, referrer: &Path, renaming: &Renaming) -> String {
+        let contents = rename::rewrite_wiki_links(contents, renaming);
+        rename::rewrite_markdown_links(&contents, referrer, renaming)
+    }
+
+    fn render(&self, body: &str) -> Vec<Block> {
+        markdown::parse(body)
+    }
+
+    fn title_metadata(&self, title: &str) -> Option<String> {
+        Some(format!("---\ntitle: {}\n---\n\n", title))
+    }
+
+    fn heading_line(&self, title: &str) -> String {
+        format!("# {}\n\n", title)
+    }
+}
+
+impl NoteFormat for Org {
+    fn split_metadata<'a>(&self, contents: &'a str) -> (FrontMatter, &'a str) {
+        org::split_keywords(contents)
+    }
+
+    fn heading<'a>(&self, body: &'a str) -> Option<&'a str> {
+        org::heading(body)
+    }
+
+    fn tags(&self, front_matter: &FrontMatter, body: &str) -> Vec<Arc<str>> {
+        org::tags(front_matter, body)
+    }
+
+    fn links(&self, body: &str) -> Vec<Arc<str>> {
+        org::links(body)
+    }
+
+    fn rewrite_links(&self, contents: &str, referrer: &Path, renaming: &Renaming) -> String {
+        org::rewrite_links(contents, referrer, renaming)
+    }
+
+    fn render(&self, body: &str) -> Vec<Block> {
+        org::parse(body)
+    }
+
+    fn title_metadata(&self, title: &str) -> Option<String> {
+        Some(format!("#+TITLE: {}\n\n", title))
+    }
+
+    fn heading_line(&self, title: &str) -> String {
+        format!("* {}\n\n", title)
+    }
+}
+
+/// Text with no markup. `#tags` and `[[links]]` still work, as they're
+/// plain enough to type anywhere.
+impl NoteFormat for PlainText {
+    fn split_metadata<'a>(&self, contents: &'a str) -> (FrontMatter, &'a str) {
+        (FrontMatter::default(), contents)
+    }
+
+    fn heading<'a>(&self, _body: &'a str) -> Option<&'a str> {
+        None
+    }
+
+    fn tags(&self, front_matter: &FrontMatter, body: &str) -> Vec<Arc<str>> {
+        tags::extract(front_matter, body)
+    }
+
+    fn links(&self, body: &str) -> Vec<Arc<str>> {
+        links::extract(body)
+    }
+
+    fn rewrite_links(&self, contents: &str, _referrer: &Path, renaming: &Renaming) -> String {
+        rename::rewrite_wiki_links(contents, renaming)
+    }
+
+    fn render(&self, body: &str) -> Vec<Block> {
+        // Line breaks are kept as written, with a block per paragraph
+        body.split("\n\n")
+            .map(|paragraph| paragraph.trim_matches('\n'))
+            .filter(|paragraph| !paragraph.trim().is_empty())
+            .map(|paragraph| {
+                Block::Paragraph(vec![Span {
+                    text: paragraph.to_string(),
+                    style: Style::default(),
+                }])
+            })
+            .collect()
+    }
+
+    fn title_metadata(&self, _title: &str) -> Option<String> {
+        None
+    }
+
+    fn heading_line(&self, title: &str) -> String {
+        format!("{}\n\n", title)
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+/// Key/value metadata from the `---` (YAML) or `+++` (TOML) block at the top
+/// of a note. Only the flat subset notes actually use is understood: scalar
+/// values and lists of scalars. Every value is kept as a list of strings so
+/// that `tags: foo` and `tags: [foo, bar]` can be treated the same way.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrontMatter {
+    pub fields: BTreeMap<String, Vec<String>>,
+}
+
+impl FrontMatter {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// The first value of `key`, for fields that only make sense as scalars
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .get(key)
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    /// All the values of `key`, empty when the key isn't there
+    pub fn get_all(&self, key: &str) -> &[String] {
+        self.fields.get(key).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// Splits a note into its front matter and the rest of the note. Notes
+/// without front matter come back with an empty `FrontMatter` and the whole
+/// note as the body.
+pub fn parse(contents: &str) -> (FrontMatter, &str) {
+    match split(contents) {
+        Some((delimiter, block, body)) => {
+            let front_matter = if delimiter == "+++" {
+                parse_toml(block)
+            } else {
+                parse_yaml(block)
+            };
+            (front_matter, body)
+        }
+        None => (FrontMatter::default(), contents),
+    }
+}
+
+/// Finds the front matter block, returning its delimiter, the block between
+/// the delimiters and everything after the closing delimiter.
+fn split(contents: &str) -> Option<(&'static str, &str, &str)> {
+    let contents = contents.trim_start_matches('\u{feff}');
+    let delimiter = ["---", "+++"]
+        .iter()
+        .copied()
+        .find(|d| contents.lines().next().map(str::trim_end) == Some(*d))?;
+
+    let block_start = contents.find('\n')? + 1;
+    let mut offset = block_start;
+    while offset < contents.len() {
+        let line_end = contents[offset..]
+            .find('\n')
+            .map(|i| offset + i + 1)
+            .unwrap_or(contents.len());
+        if contents[offset..line_end].trim_end() == delimiter {
+            let block = &contents[block_start..offset];
+            let body = contents[line_end..].trim_start_matches(|c| c == '\r' || c == '\n');
+            return Some((delimiter, block, body));
+        }
+        offset = line_end;
+    }
+    None
+}
+
+fn parse_yaml(block: &str) -> FrontMatter {
+    let mut fields: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut current_key: Option<String> = None;
+
+    for line in block.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        // `- item` lines continue a list under the last key
+        if line.trim_start().starts_with("- ") {
+            if let Some(key) = &current_key {
+                fields
+                    .entry(key.clone())
+                    .or_default()
+                    .push(unquote(&line.trim_start()[2..]).to_string());
+            }
+            continue;
+        }
+
+        if let Some(colon) = line.find(':') {
+            let key = line[..colon].trim().to_lowercase();
+            let value = line[colon + 1..].trim();
+            if value.is_empty() {
+                fields.entry(key.clone()).or_default();
+            } else {
+                fields.insert(key.clone(), values(value));
+            }
+            current_key = Some(key);
+        }
+    }
+
+    FrontMatter { fields }
+}
+
+fn parse_toml(block: &str) -> FrontMatter {
+    let mut fields = BTreeMap::new();
+
+    for line in block.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+        if let Some(equals) = line.find('=') {
+            fields.insert(
+                line[..equals].trim().to_lowercase(),
+                values(line[equals + 1..].trim()),
+            );
+        }
+    }
+
+    FrontMatter { fields }
+}
+
+/// Reads a scalar or an inline `[a, b]` list
+fn values(value: &str) -> Vec<String> {
+    if value.starts_with('[') && value.ends_with(']') {
+        value[1..value.len() - 1]
+            .split(',')
+            .map(|item| unquote(item).to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    } else {
+        vec![unquote(value).to_string()]
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches(|c| c == '"' || c == '\'')
+}
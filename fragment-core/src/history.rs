@@ -0,0 +1,148 @@
+//! Queries that have been searched for, kept in `.fragment/history.toml` in
+//! the notes root as one `"query" = [uses, last used]` line each, most
+//! recent first. Times are seconds since the Unix epoch.
+
+use std::cmp::Reverse;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::saved::{quote, unquote};
+
+/// How many queries are remembered; the least recent go first
+pub const LIMIT: usize = 200;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub query: String,
+    /// How many times it's been searched for
+    pub uses: u32,
+    /// When it was last searched for
+    pub last_used: u64,
+}
+
+impl Entry {
+    /// Frequency weighted by recency: a use today counts for ten from
+    /// months ago
+    fn score(&self, now: u64) -> u64 {
+        let days_old = now.saturating_sub(self.last_used) / 86_400;
+        let weight = match days_old {
+            0 => 100,
+            1..=6 => 70,
+            7..=29 => 50,
+            30..=89 => 30,
+            _ => 10,
+        };
+        u64::from(self.uses) * weight
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct History {
+    /// Most recent first
+    entries: Vec<Entry>,
+}
+
+fn file(root: &str) -> PathBuf {
+    Path::new(root).join(".fragment").join("history.toml")
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
+}
+
+impl History {
+    /// The history for `root`, or an empty one if there's no file yet
+    pub fn load(root: &str) -> History {
+        match std::fs::read_to_string(file(root)) {
+            Ok(contents) => History::parse(&contents),
+            Err(_) => History::default(),
+        }
+    }
+
+    pub fn save(&self, root: &str) -> io::Result<()> {
+        let path = file(root);
+        if let Some(folder) = path.parent() {
+            std::fs::create_dir_all(folder)?;
+        }
+        let mut contents = String::new();
+        for entry in &self.entries {
+            contents.push_str(&format!(
+                "{} = [{}, {}]\n",
+                quote(&entry.query),
+                entry.uses,
+                entry.last_used
+            ));
+        }
+        std::fs::write(path, contents)
+    }
+
+    fn parse(contents: &str) -> History {
+        let mut entries: Vec<Entry> = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (query, rest) = unquote(line)?;
+                let rest = rest.trim_start().strip_prefix('=')?.trim();
+                let rest = rest.strip_prefix('[')?.strip_suffix(']')?;
+                let mut numbers = rest.split(',').map(str::trim);
+                let uses = numbers.next()?.parse().ok()?;
+                let last_used = numbers.next()?.parse().ok()?;
+                Some(Entry {
+                    query,
+                    uses,
+                    last_used,
+                })
+            })
+            .collect();
+        entries.sort_by_key(|entry| Reverse(entry.last_used));
+        entries.truncate(LIMIT);
+        History { entries }
+    }
+
+    /// Notes a search for `query` at `time`. Blank queries aren't kept.
+    pub fn record(&mut self, query: &str, time: SystemTime) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        let uses = match self.entries.iter().position(|entry| entry.query == query) {
+            Some(i) => self.entries.remove(i).uses + 1,
+            None => 1,
+        };
+        self.entries.insert(
+            0,
+            Entry {
+                query: query.to_string(),
+                uses,
+                last_used: seconds(time),
+            },
+        );
+        self.entries.truncate(LIMIT);
+    }
+
+    /// Every remembered query, most recent first
+    pub fn recent(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Every remembered query, the most used recently first
+    pub fn ranked(&self, now: SystemTime) -> Vec<&Entry> {
+        let now = seconds(now);
+        let mut ranked: Vec<&Entry> = self.entries.iter().collect();
+        // Stable, so ties stay most recent first
+        ranked.sort_by_key(|entry| Reverse(entry.score(now)));
+        ranked
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,88 @@
+//! Notes as they were last read, so a search only reads the files that have
+//! changed since the one before. The index belongs to whoever runs the
+//! searches, and forgets the notes a watcher says were changed or removed.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::note::Note;
+use crate::search;
+use crate::title::TitleStrategy;
+use crate::watch::Batch;
+
+/// How long after its last change a file has to be read for its note to be
+/// kept. Some file systems only keep modified times to the second, so an
+/// edit just after a read that doesn't change the size can't be told apart
+/// from the file that was read.
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+pub struct Index {
+    notes: HashMap<PathBuf, Indexed>,
+}
+
+/// A note, with what it was read under
+struct Indexed {
+    modified: SystemTime,
+    size: u64,
+    titles: TitleStrategy,
+    note: Note,
+}
+
+impl Index {
+    pub fn new() -> Index {
+        Index::default()
+    }
+
+    /// Reads the note at `path` like [`search::read_note`], unless it's
+    /// indexed and its modified time and size say it hasn't changed since
+    pub fn read_note(&mut self, root: &str, path: &Path, titles: TitleStrategy) -> Option<Note> {
+        let metadata = path.metadata().ok()?;
+        if let Some(indexed) = self.notes.get(path) {
+            if Some(indexed.modified) == metadata.modified().ok()
+                && indexed.size == metadata.len()
+                && indexed.titles == titles
+            {
+                return Some(Note {
+                    folder: search::relative_folder(Path::new(root), path).into(),
+                    ..indexed.note.clone()
+                });
+            }
+        }
+
+        let read_at = SystemTime::now();
+        let note = search::read_note(root, path, titles)?;
+        let settled = read_at
+            .duration_since(note.modified)
+            .map_or(false, |age| age >= SETTLE_TIME);
+        if settled {
+            self.notes.insert(
+                path.to_path_buf(),
+                Indexed {
+                    modified: note.modified,
+                    size: note.size,
+                    titles,
+                    note: note.clone(),
+                },
+            );
+        } else {
+            self.notes.remove(path);
+        }
+        Some(note)
+    }
+
+    /// Drops the notes a batch of changes touched, so edited ones are read
+    /// again and removed ones don't linger
+    pub fn forget(&mut self, batch: &Batch) {
+        self.notes.retain(|path, _| !batch.affects(path));
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+}
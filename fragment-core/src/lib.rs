@@ -0,0 +1,29 @@
+//! Walking, indexing and searching a folder of notes, with no UI. The
+//! fragment app is one frontend on top of this; anything else that wants to
+//! search notes the same way can use it too.
+
+pub mod config;
+pub mod editor;
+pub mod format;
+pub mod frontmatter;
+pub mod history;
+pub mod index;
+pub mod links;
+pub mod markdown;
+pub mod note;
+pub mod org;
+pub mod pins;
+pub mod query;
+pub mod rename;
+pub mod saved;
+pub mod scope;
+pub mod search;
+pub mod session;
+pub mod tags;
+pub mod tasks;
+pub mod title;
+pub mod undo;
+pub mod watch;
+
+pub use note::Note;
+pub use search::{search, SearchResults};
@@ -0,0 +1,71 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::note::Note;
+
+/// Targets of every `[[wiki-link]]` in a note, in order and without
+/// duplicates. `[[Target|label]]` and `[[Target#heading]]` both link to
+/// `Target`.
+pub fn extract(body: &str) -> Vec<Arc<str>> {
+    let mut links: Vec<Arc<str>> = vec![];
+    let mut rest = body;
+
+    while let Some(start) = rest.find("[[") {
+        rest = &rest[start + 2..];
+        let end = match rest.find("]]") {
+            Some(end) => end,
+            None => break,
+        };
+        let target = link_target(&rest[..end]);
+        if !target.is_empty()
+            && !target.contains('\n')
+            && !links.iter().any(|link| link.as_ref() == target)
+        {
+            links.push(target.into());
+        }
+        rest = &rest[end + 2..];
+    }
+
+    links
+}
+
+/// The note a link's inner text points at, without any label or heading
+pub fn link_target(inner: &str) -> &str {
+    inner
+        .split(|c| c == '|' || c == '#')
+        .next()
+        .unwrap_or("")
+        .trim()
+}
+
+/// Whether a `[[link]]` to `target` means `note`: its title, file name or
+/// one of its aliases, ignoring case.
+pub fn resolves_to(target: &str, note: &Note) -> bool {
+    let target = target.to_lowercase();
+    let stem = Path::new(note.file_name.as_ref())
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase());
+
+    note.title.to_lowercase() == target
+        || stem.as_deref() == Some(target.as_str())
+        || note
+            .aliases
+            .iter()
+            .any(|alias| alias.to_lowercase() == target)
+}
+
+/// Notes with a link that resolves to `note`
+pub fn backlinks<N: AsRef<Note> + Clone>(note: &Note, notes: &[N]) -> Vec<N> {
+    notes
+        .iter()
+        .filter(|other| other.as_ref().path != note.path)
+        .filter(|other| {
+            other
+                .as_ref()
+                .links
+                .iter()
+                .any(|link| resolves_to(link, note))
+        })
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,328 @@
+//! A small markdown parser for the preview pane. It covers what notes
+//! actually use: headings, emphasis, inline code, fenced code blocks, lists,
+//! blockquotes, rules and links. The output is a plain list of blocks with
+//! styled spans, independent of the UI, so it can be tested on its own.
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub strikethrough: bool,
+    pub link: Option<Link>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Link {
+    /// `[text](url)`
+    Url(String),
+    /// `[[Note Title]]`
+    Note(String),
+}
+
+/// A run of text with a single style
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Block {
+    Heading {
+        level: usize,
+        spans: Vec<Span>,
+    },
+    Paragraph(Vec<Span>),
+    CodeBlock {
+        language: Option<String>,
+        code: String,
+    },
+    ListItem {
+        /// Nesting level, 0 for top-level items
+        depth: usize,
+        /// `-` for bullets, `1.` etc. for numbered items
+        marker: String,
+        /// `Some` for `- [ ]` / `- [x]` task items
+        checked: Option<bool>,
+        spans: Vec<Span>,
+    },
+    Quote(Vec<Span>),
+    Rule,
+}
+
+pub fn parse(text: &str) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut paragraph: Vec<&str> = vec![];
+    let mut quote: Vec<&str> = vec![];
+    let mut lines = text.lines();
+
+    // Paragraphs and quotes run over several lines, so they're collected
+    // until something else starts.
+    fn flush(blocks: &mut Vec<Block>, paragraph: &mut Vec<&str>, quote: &mut Vec<&str>) {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(parse_inline(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+        if !quote.is_empty() {
+            blocks.push(Block::Quote(parse_inline(&quote.join(" "))));
+            quote.clear();
+        }
+    }
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+
+        if let Some(fence) = code_fence(trimmed) {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+            let language = trimmed[fence.len()..].trim();
+            let mut code = vec![];
+            for line in &mut lines {
+                if line.trim_start().starts_with(fence) {
+                    break;
+                }
+                code.push(line);
+            }
+            blocks.push(Block::CodeBlock {
+                language: if language.is_empty() {
+                    None
+                } else {
+                    Some(language.to_string())
+                },
+                code: code.join("\n"),
+            });
+        } else if trimmed.is_empty() {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+        } else if let Some((level, heading)) = heading(trimmed) {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+            blocks.push(Block::Heading {
+                level,
+                spans: parse_inline(heading),
+            });
+        } else if is_rule(trimmed) {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+            blocks.push(Block::Rule);
+        } else if trimmed.starts_with('>') {
+            if !paragraph.is_empty() {
+                flush(&mut blocks, &mut paragraph, &mut quote);
+            }
+            quote.push(trimmed[1..].trim());
+        } else if let Some((marker, item)) = list_item(trimmed) {
+            flush(&mut blocks, &mut paragraph, &mut quote);
+            let indent = line.len() - trimmed.len();
+            let (checked, item) = task(item);
+            blocks.push(Block::ListItem {
+                depth: indent / 2,
+                marker,
+                checked,
+                spans: parse_inline(item),
+            });
+        } else {
+            if !quote.is_empty() {
+                flush(&mut blocks, &mut paragraph, &mut quote);
+            }
+            paragraph.push(trimmed);
+        }
+    }
+    flush(&mut blocks, &mut paragraph, &mut quote);
+
+    blocks
+}
+
+fn code_fence(line: &str) -> Option<&'static str> {
+    if line.starts_with("```") {
+        Some("```")
+    } else if line.starts_with("~~~") {
+        Some("~~~")
+    } else {
+        None
+    }
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &line[level..];
+    if rest.is_empty() {
+        Some((level, ""))
+    } else if rest.starts_with(' ') {
+        Some((level, rest.trim().trim_end_matches('#').trim_end()))
+    } else {
+        None
+    }
+}
+
+fn is_rule(line: &str) -> bool {
+    let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    line.len() >= 3
+        && ["-", "*", "_"]
+            .iter()
+            .any(|c| line.chars().all(|l| l.to_string() == *c))
+}
+
+fn list_item(line: &str) -> Option<(String, &str)> {
+    for bullet in &["- ", "* ", "+ "] {
+        if line.starts_with(bullet) {
+            return Some(("-".to_string(), &line[2..]));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && digits <= 9 {
+        let rest = &line[digits..];
+        if rest.starts_with(". ") || rest.starts_with(") ") {
+            return Some((line[..digits + 1].to_string(), &rest[2..]));
+        }
+    }
+    None
+}
+
+fn task(item: &str) -> (Option<bool>, &str) {
+    if item.starts_with("[ ] ") {
+        (Some(false), &item[4..])
+    } else if item.starts_with("[x] ") || item.starts_with("[X] ") {
+        (Some(true), &item[4..])
+    } else {
+        (None, item)
+    }
+}
+
+/// Splits a line of markdown into styled spans
+pub fn parse_inline(text: &str) -> Vec<Span> {
+    let mut spans = vec![];
+    parse_inline_into(text, &Style::default(), &mut spans);
+    spans
+}
+
+fn parse_inline_into(text: &str, style: &Style, spans: &mut Vec<Span>) {
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        // A backslash escapes punctuation, and is kept before anything else
+        // so Windows paths survive
+        let escaped = rest[c.len_utf8()..].chars().next();
+        if let (Some(escaped), '\\') = (escaped, c) {
+            if escaped.is_ascii_punctuation() {
+                plain.push(escaped);
+                rest = &rest[1 + escaped.len_utf8()..];
+                continue;
+            }
+        }
+
+        if let Some((consumed, inner, inner_style, nested)) = styled_run(rest, style) {
+            push_plain(&mut plain, style, spans);
+            if nested {
+                parse_inline_into(inner, &inner_style, spans);
+            } else {
+                spans.push(Span {
+                    text: inner.to_string(),
+                    style: inner_style,
+                });
+            }
+            rest = &rest[consumed..];
+            continue;
+        }
+
+        plain.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    push_plain(&mut plain, style, spans);
+}
+
+fn push_plain(plain: &mut String, style: &Style, spans: &mut Vec<Span>) {
+    if !plain.is_empty() {
+        spans.push(Span {
+            text: std::mem::take(plain),
+            style: style.clone(),
+        });
+    }
+}
+
+/// Recognises a styled run at the start of `text`, returning how much of
+/// `text` it takes up, its contents, their style and whether the contents
+/// can hold more markdown.
+fn styled_run<'a>(text: &'a str, style: &Style) -> Option<(usize, &'a str, Style, bool)> {
+    if text.starts_with('`') {
+        let end = text[1..].find('`')?;
+        let style = Style {
+            code: true,
+            ..style.clone()
+        };
+        return Some((end + 2, &text[1..end + 1], style, false));
+    }
+
+    if text.starts_with("[[") {
+        let end = text.find("]]")?;
+        let inner = &text[2..end];
+        let target = inner.split(|c| c == '|' || c == '#').next()?.trim();
+        let label = inner.rfind('|').map(|i| &inner[i + 1..]).unwrap_or(inner);
+        let style = Style {
+            link: Some(Link::Note(target.to_string())),
+            ..style.clone()
+        };
+        return Some((end + 2, label, style, false));
+    }
+
+    if text.starts_with('[') {
+        let close = text.find("](")?;
+        let end = close + 2 + text[close + 2..].find(')')?;
+        let url = &text[close + 2..end];
+        let style = Style {
+            link: Some(Link::Url(url.to_string())),
+            ..style.clone()
+        };
+        return Some((end + 1, &text[1..close], style, true));
+    }
+
+    for (delimiter, apply) in &[
+        ("**", Emphasis::Bold),
+        ("__", Emphasis::Bold),
+        ("~~", Emphasis::Strikethrough),
+        ("*", Emphasis::Italic),
+        ("_", Emphasis::Italic),
+    ] {
+        if let Some(inner) = delimited(text, delimiter) {
+            let mut style = style.clone();
+            match apply {
+                Emphasis::Bold => style.bold = true,
+                Emphasis::Italic => style.italic = true,
+                Emphasis::Strikethrough => style.strikethrough = true,
+            }
+            return Some((inner.len() + delimiter.len() * 2, inner, style, true));
+        }
+    }
+
+    None
+}
+
+enum Emphasis {
+    Bold,
+    Italic,
+    Strikethrough,
+}
+
+/// The text between `delimiter`s at the start of `text`. Like CommonMark,
+/// the contents can't start or end with whitespace, so `2 * 3 * 4` and
+/// `snake_case_names` stay as they are.
+fn delimited<'a>(text: &'a str, delimiter: &str) -> Option<&'a str> {
+    if !text.starts_with(delimiter) {
+        return None;
+    }
+    let after = &text[delimiter.len()..];
+    let end = after.find(delimiter)?;
+    let inner = &after[..end];
+    let next = after[end + delimiter.len()..].chars().next();
+
+    let well_formed = !inner.is_empty()
+        && !inner.starts_with(char::is_whitespace)
+        && !inner.ends_with(char::is_whitespace)
+        && !(delimiter.starts_with('_') && next.map_or(false, char::is_alphanumeric));
+    if well_formed {
+        Some(inner)
+    } else {
+        None
+    }
+}
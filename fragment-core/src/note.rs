@@ -0,0 +1,74 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::editor;
+use crate::format;
+use crate::frontmatter::FrontMatter;
+use crate::rename;
+use crate::tasks::Task;
+use crate::title::TitleStrategy;
+
+/// Everything the index knows about one note
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    pub path: Arc<str>,
+    pub file_name: Arc<str>,
+    pub modified: SystemTime,
+    pub created: Option<SystemTime>,
+    /// Parent directory relative to the notes root, empty for the root itself
+    pub folder: Arc<str>,
+    pub size: u64,
+    pub word_count: usize,
+    pub tags: Arc<Vec<Arc<str>>>,
+    pub aliases: Arc<Vec<Arc<str>>>,
+    /// Targets of the note's `[[wiki-links]]`
+    pub links: Arc<Vec<Arc<str>>>,
+    /// `- [ ]` checkboxes, open and done
+    pub tasks: Arc<Vec<Task>>,
+    pub front_matter: Arc<FrontMatter>,
+    /// Display title, worked out by the active `TitleStrategy`
+    pub title: Arc<str>,
+    pub found_line: Option<Arc<str>>,
+    /// 1-based line and column of the first match, for jumping to it in an editor
+    pub found_at: Option<(u64, u64)>,
+}
+
+impl AsRef<Note> for Note {
+    fn as_ref(&self) -> &Note {
+        self
+    }
+}
+
+impl Note {
+    /// Opens the note with the editor command `template`, at the first match
+    pub fn open_note_in_editor(&self, template: Option<&str>) -> io::Result<()> {
+        let (line, col) = self.found_at.unwrap_or((1, 1));
+        editor::open(template, Path::new(self.path.as_ref()), line, col)
+    }
+}
+
+/// Creates a note called `title` in `folder`, with the given extension, unless
+/// there's already one. Its starting contents depend on the title strategy so
+/// that it comes out with `title` as its title. Returns the note's path, or
+/// an `InvalidInput` error for a blank title.
+pub fn create(
+    folder: &Path,
+    title: &str,
+    extension: &str,
+    titles: TitleStrategy,
+) -> io::Result<PathBuf> {
+    if title.trim().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "new notes need a title",
+        ));
+    }
+    let path = folder.join(rename::with_extension(title, extension));
+    if !path.exists() {
+        let format = format::for_path(&path);
+        std::fs::write(&path, titles.new_note_contents(format, title))?;
+    }
+    Ok(path)
+}
@@ -0,0 +1,413 @@
+//! Org-mode notes: `#+KEYWORD:` metadata, `* headings` with `:tags:`,
+//! `[[target][description]]` links and the markup the preview can show.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::frontmatter::FrontMatter;
+use crate::markdown::{Block, Link, Span, Style};
+use crate::rename::Renaming;
+
+/// Reads the `#+KEY: value` lines at the top of a note as its front matter,
+/// with keys lowercased so `#+TITLE:` is found as `title`. `#+FILETAGS:`
+/// becomes `tags`.
+pub fn split_keywords(contents: &str) -> (FrontMatter, &str) {
+    let mut fields: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut offset = 0;
+
+    for line in contents.split('\n') {
+        let trimmed = line.trim();
+        let keyword = keyword(trimmed);
+        if keyword.is_none() && !trimmed.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = keyword {
+            match key.as_str() {
+                "filetags" | "tags" => fields
+                    .entry("tags".to_string())
+                    .or_default()
+                    .extend(tag_list(value).map(str::to_string)),
+                _ => fields.entry(key).or_default().push(value.to_string()),
+            }
+        }
+        offset = (offset + line.len() + 1).min(contents.len());
+    }
+
+    (FrontMatter { fields }, &contents[offset..])
+}
+
+/// `#+KEY: value`, but not the `#+BEGIN_...` lines that open blocks
+fn keyword(line: &str) -> Option<(String, &str)> {
+    if !line.starts_with("#+") {
+        return None;
+    }
+    let colon = line.find(':')?;
+    let key = line[2..colon].to_lowercase();
+    if key.is_empty() || key.starts_with("begin_") || key.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((key, line[colon + 1..].trim()))
+}
+
+/// `:work:project:` or `work project`
+fn tag_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+}
+
+/// A headline's stars, text and trailing `:tags:`
+fn headline(line: &str) -> Option<(usize, &str, Vec<&str>)> {
+    let level = line.chars().take_while(|c| *c == '*').count();
+    if level == 0 || !line[level..].starts_with(' ') {
+        return None;
+    }
+    let text = line[level..].trim();
+    let last_word = text.rsplit(char::is_whitespace).next().unwrap_or("");
+    if last_word.len() > 2 && last_word.starts_with(':') && last_word.ends_with(':') {
+        let text = text[..text.len() - last_word.len()].trim_end();
+        Some((level, text, tag_list(last_word).collect()))
+    } else {
+        Some((level, text, vec![]))
+    }
+}
+
+/// The text of the first headline
+pub fn heading(body: &str) -> Option<&str> {
+    body.lines().find_map(headline).map(|(_, text, _)| text)
+}
+
+/// Headline tags followed by `#+FILETAGS:`, without duplicates
+pub fn tags(front_matter: &FrontMatter, body: &str) -> Vec<Arc<str>> {
+    let mut tags: Vec<Arc<str>> = vec![];
+    let headline_tags = body
+        .lines()
+        .filter_map(headline)
+        .flat_map(|(_, _, tags)| tags);
+    let file_tags = front_matter.get_all("tags").iter().map(String::as_str);
+
+    for tag in headline_tags.chain(file_tags) {
+        if !tags.iter().any(|t| t.as_ref() == tag) {
+            tags.push(tag.into());
+        }
+    }
+    tags
+}
+
+/// Notes linked with `[[file:note.org][...]]` or `[[Note]]`, in order and
+/// without duplicates. Web links and links within the note are left out.
+pub fn links(body: &str) -> Vec<Arc<str>> {
+    let mut links: Vec<Arc<str>> = vec![];
+    let mut rest = body;
+
+    while let Some(start) = rest.find("[[") {
+        rest = &rest[start + 2..];
+        let end = match rest.find("]]") {
+            Some(end) => end,
+            None => break,
+        };
+        let (target, _) = split_link(&rest[..end]);
+        if let Some(Link::Note(target)) = link(target) {
+            if !target.contains('\n') && !links.iter().any(|link| link.as_ref() == target) {
+                links.push(target.into());
+            }
+        }
+        rest = &rest[end + 2..];
+    }
+
+    links
+}
+
+/// Points `[[file:old.org][...]]` and `[[Old Name]]` links to a note being
+/// renamed at its new name, keeping descriptions and `::` search options
+pub fn rewrite_links(contents: &str, referrer: &Path, renaming: &Renaming) -> String {
+    let mut out = String::with_capacity(contents.len());
+    let mut rest = contents;
+
+    while let Some(start) = rest.find("[[") {
+        out.push_str(&rest[..start + 2]);
+        rest = &rest[start + 2..];
+        let end = match rest.find("]]") {
+            Some(end) => end,
+            None => break,
+        };
+        let (target, description) = split_link(&rest[..end]);
+        out.push_str(&rewrite_target(target, referrer, renaming));
+        if let Some(description) = description {
+            out.push_str("][");
+            out.push_str(description);
+        }
+        rest = &rest[end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn rewrite_target(target: &str, referrer: &Path, renaming: &Renaming) -> String {
+    if let Some(Link::Note(_)) = link(target) {
+        let trimmed = target.trim();
+        if let Some(path) = trimmed.strip_prefix("file:") {
+            let (path, search) = match path.find("::") {
+                Some(i) => (&path[..i], &path[i..]),
+                None => (path, ""),
+            };
+            if renaming.is_file(referrer, path) {
+                let folder = path.rfind('/').map(|slash| &path[..=slash]).unwrap_or("");
+                return format!("file:{}{}{}", folder, renaming.new_file_name(), search);
+            }
+        } else if renaming.is_old_name(trimmed) {
+            return renaming.new_title.to_string();
+        }
+    }
+    target.to_string()
+}
+
+/// Splits `target][description` into its two halves
+fn split_link(inner: &str) -> (&str, Option<&str>) {
+    match inner.find("][") {
+        Some(i) => (&inner[..i], Some(&inner[i + 2..])),
+        None => (inner, None),
+    }
+}
+
+/// What a link target points at. `file:` links to notes are given as the
+/// file's stem, the way wiki-links name notes.
+fn link(target: &str) -> Option<Link> {
+    let target = target.trim();
+    if target.contains("://") || target.starts_with("mailto:") {
+        return Some(Link::Url(target.to_string()));
+    }
+    // `*Heading`, `#custom-id` and `id:...` point inside notes
+    if target.is_empty()
+        || target.starts_with('*')
+        || target.starts_with('#')
+        || target.starts_with("id:")
+    {
+        return None;
+    }
+    let target = target.trim_start_matches("file:");
+    let target = target.split("::").next().unwrap_or(target);
+    let name = target.rsplit('/').next().unwrap_or(target);
+    let stem = match name.rfind('.') {
+        Some(dot) if dot > 0 => &name[..dot],
+        _ => name,
+    };
+    Some(Link::Note(stem.to_string()))
+}
+
+pub fn parse(text: &str) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut paragraph: Vec<&str> = vec![];
+    let mut lines = text.lines();
+
+    fn flush(blocks: &mut Vec<Block>, paragraph: &mut Vec<&str>) {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(parse_inline(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    }
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        let lower = trimmed.to_lowercase();
+
+        if lower.starts_with("#+begin_") {
+            flush(&mut blocks, &mut paragraph);
+            let kind = lower["#+begin_".len()..]
+                .split_whitespace()
+                .next()
+                .unwrap_or("")
+                .to_string();
+            let end = format!("#+end_{}", kind);
+            let mut contents = vec![];
+            for line in &mut lines {
+                if line.trim().to_lowercase().starts_with(&end) {
+                    break;
+                }
+                contents.push(line);
+            }
+            if kind == "quote" {
+                let quote: Vec<&str> = contents.iter().map(|line| line.trim()).collect();
+                blocks.push(Block::Quote(parse_inline(&quote.join(" "))));
+            } else {
+                // `#+BEGIN_SRC rust`
+                let language = trimmed
+                    .get("#+begin_".len() + kind.len()..)
+                    .unwrap_or("")
+                    .split_whitespace()
+                    .next()
+                    .filter(|_| kind == "src")
+                    .map(str::to_string);
+                blocks.push(Block::CodeBlock {
+                    language,
+                    code: contents.join("\n"),
+                });
+            }
+        } else if trimmed.is_empty() {
+            flush(&mut blocks, &mut paragraph);
+        } else if trimmed.starts_with("#+") || trimmed == "#" || trimmed.starts_with("# ") {
+            // Keywords and comments aren't shown
+            flush(&mut blocks, &mut paragraph);
+        } else if let Some((level, text, _)) = headline(line) {
+            flush(&mut blocks, &mut paragraph);
+            blocks.push(Block::Heading {
+                level: level.min(6),
+                spans: parse_inline(text),
+            });
+        } else if trimmed.len() >= 5 && trimmed.chars().all(|c| c == '-') {
+            flush(&mut blocks, &mut paragraph);
+            blocks.push(Block::Rule);
+        } else if let Some((marker, item)) = list_item(trimmed) {
+            flush(&mut blocks, &mut paragraph);
+            let indent = line.len() - trimmed.len();
+            let (checked, item) = checkbox(item);
+            blocks.push(Block::ListItem {
+                depth: indent / 2,
+                marker,
+                checked,
+                spans: parse_inline(item),
+            });
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+    flush(&mut blocks, &mut paragraph);
+
+    blocks
+}
+
+fn list_item(line: &str) -> Option<(String, &str)> {
+    // `* ` at the start of a line is a headline, so only indented stars are bullets
+    for bullet in &["- ", "+ "] {
+        if line.starts_with(bullet) {
+            return Some(("-".to_string(), &line[2..]));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && digits <= 9 {
+        let rest = &line[digits..];
+        if rest.starts_with(". ") || rest.starts_with(") ") {
+            return Some((line[..digits + 1].to_string(), &rest[2..]));
+        }
+    }
+    None
+}
+
+/// `[ ]`, `[X]` and the partly done `[-]`
+fn checkbox(item: &str) -> (Option<bool>, &str) {
+    if item.starts_with("[ ] ") || item.starts_with("[-] ") {
+        (Some(false), &item[4..])
+    } else if item.starts_with("[X] ") || item.starts_with("[x] ") {
+        (Some(true), &item[4..])
+    } else {
+        (None, item)
+    }
+}
+
+/// Splits a line of org markup into styled spans
+pub fn parse_inline(text: &str) -> Vec<Span> {
+    let mut spans = vec![];
+    parse_inline_into(text, &Style::default(), &mut spans);
+    spans
+}
+
+fn parse_inline_into(text: &str, style: &Style, spans: &mut Vec<Span>) {
+    let mut plain = String::new();
+    let mut rest = text;
+    let mut previous = None;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((consumed, inner, inner_style, nested)) = styled_run(rest, previous, style) {
+            if !plain.is_empty() {
+                spans.push(Span {
+                    text: std::mem::take(&mut plain),
+                    style: style.clone(),
+                });
+            }
+            if nested {
+                parse_inline_into(inner, &inner_style, spans);
+            } else {
+                spans.push(Span {
+                    text: inner.to_string(),
+                    style: inner_style,
+                });
+            }
+            previous = rest[..consumed].chars().last();
+            rest = &rest[consumed..];
+            continue;
+        }
+
+        plain.push(c);
+        previous = Some(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    if !plain.is_empty() {
+        spans.push(Span {
+            text: plain,
+            style: style.clone(),
+        });
+    }
+}
+
+/// Recognises a link or emphasis at the start of `text`, returning how much
+/// of `text` it takes up, its contents, their style and whether the
+/// contents can hold more markup. `previous` is the character before `text`.
+fn styled_run<'a>(
+    text: &'a str,
+    previous: Option<char>,
+    style: &Style,
+) -> Option<(usize, &'a str, Style, bool)> {
+    if text.starts_with("[[") {
+        let end = text.find("]]")?;
+        let (target, description) = split_link(&text[2..end]);
+        let style = Style {
+            link: link(target),
+            ..style.clone()
+        };
+        return Some((end + 2, description.unwrap_or(target), style, true));
+    }
+
+    // Emphasis markers only count at word boundaries, so `a/b/c` and
+    // `x*y*z` are left alone
+    if previous.map_or(false, char::is_alphanumeric) {
+        return None;
+    }
+    let marker = text.chars().next()?;
+    let mut style = style.clone();
+    let nested = match marker {
+        '*' => {
+            style.bold = true;
+            true
+        }
+        '/' => {
+            style.italic = true;
+            true
+        }
+        '+' => {
+            style.strikethrough = true;
+            true
+        }
+        '=' | '~' => {
+            style.code = true;
+            false
+        }
+        _ => return None,
+    };
+
+    let after = &text[1..];
+    let end = after.find(marker)?;
+    let inner = &after[..end];
+    let next = after[end + 1..].chars().next();
+    let well_formed = !inner.is_empty()
+        && !inner.starts_with(char::is_whitespace)
+        && !inner.ends_with(char::is_whitespace)
+        && !next.map_or(false, char::is_alphanumeric);
+    if well_formed {
+        Some((end + 2, inner, style, nested))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,187 @@
+//! Notes pinned to the top of the results, and notes recently opened or
+//! previewed, kept per notes root in `.fragment/pinned.toml` and
+//! `.fragment/recent.toml`. Notes are stored by their path under the root,
+//! so the folder can move without losing them: pins as a `pinned = [...]`
+//! array, and recent notes as one `"path" = opened` line each.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::note::Note;
+use crate::saved::{quote, unquote};
+
+/// How many recently opened notes are remembered
+pub const RECENT_LIMIT: usize = 50;
+
+fn file(root: &str, name: &str) -> PathBuf {
+    Path::new(root).join(".fragment").join(name)
+}
+
+fn write(path: PathBuf, contents: String) -> io::Result<()> {
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder)?;
+    }
+    std::fs::write(path, contents)
+}
+
+/// Lines that aren't blank or comments
+fn entries(contents: &str) -> impl Iterator<Item = &str> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// `path` under `root`, as it's stored
+pub fn relative(root: &str, path: &str) -> String {
+    let path = Path::new(path);
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// The strings in the `pinned = [...]` array, over as many lines as it takes
+fn parse_pinned(contents: &str) -> Vec<String> {
+    let contents = entries(contents).collect::<Vec<_>>().join("\n");
+    let mut paths = Vec::new();
+    let mut rest = match contents
+        .strip_prefix("pinned")
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix('='))
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix('['))
+    {
+        Some(rest) => rest,
+        None => return paths,
+    };
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        match unquote(rest) {
+            Some((path, after)) => {
+                paths.push(path);
+                rest = after;
+            }
+            // The closing bracket, or something that isn't a path
+            None => return paths,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pins {
+    /// In the order they were pinned
+    paths: Vec<String>,
+}
+
+impl Pins {
+    /// The pins for `root`, or none if there's no file yet
+    pub fn load(root: &str) -> Pins {
+        let contents = std::fs::read_to_string(file(root, "pinned.toml")).unwrap_or_default();
+        Pins {
+            paths: parse_pinned(&contents),
+        }
+    }
+
+    pub fn save(&self, root: &str) -> io::Result<()> {
+        let mut contents = String::from("pinned = [\n");
+        for path in &self.paths {
+            contents.push_str(&format!("    {},\n", quote(path)));
+        }
+        contents.push_str("]\n");
+        write(file(root, "pinned.toml"), contents)
+    }
+
+    pub fn is_pinned(&self, root: &str, note: &Note) -> bool {
+        let path = relative(root, &note.path);
+        self.paths.contains(&path)
+    }
+
+    /// Pins `note` if it isn't, or unpins it if it is. Returns whether it's
+    /// pinned now.
+    pub fn toggle(&mut self, root: &str, note: &Note) -> bool {
+        let path = relative(root, &note.path);
+        match self.paths.iter().position(|pinned| pinned == &path) {
+            Some(i) => {
+                self.paths.remove(i);
+                false
+            }
+            None => {
+                self.paths.push(path);
+                true
+            }
+        }
+    }
+
+    /// Moves the pinned notes in `list` to the top, leaving both them and the
+    /// rest in the order they were in
+    pub fn pinned_first<N: AsRef<Note>>(&self, root: &str, list: &mut Vec<N>) {
+        if self.paths.is_empty() {
+            return;
+        }
+        let (mut pinned, rest): (Vec<N>, Vec<N>) = list
+            .drain(..)
+            .partition(|note| self.is_pinned(root, note.as_ref()));
+        pinned.extend(rest);
+        *list = pinned;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recent {
+    /// Paths with when they were last opened, in seconds since the Unix
+    /// epoch, most recent first
+    entries: Vec<(String, u64)>,
+}
+
+impl Recent {
+    /// The recent notes for `root`, or none if there's no file yet
+    pub fn load(root: &str) -> Recent {
+        let contents = std::fs::read_to_string(file(root, "recent.toml")).unwrap_or_default();
+        let mut entries: Vec<(String, u64)> = entries(&contents)
+            .filter_map(|line| {
+                let (path, rest) = unquote(line)?;
+                let opened = rest.trim_start().strip_prefix('=')?.trim().parse().ok()?;
+                Some((path, opened))
+            })
+            .collect();
+        entries.sort_by_key(|(_, opened)| std::cmp::Reverse(*opened));
+        entries.truncate(RECENT_LIMIT);
+        Recent { entries }
+    }
+
+    pub fn save(&self, root: &str) -> io::Result<()> {
+        let mut contents = String::new();
+        for (path, opened) in &self.entries {
+            contents.push_str(&format!("{} = {}\n", quote(path), opened));
+        }
+        write(file(root, "recent.toml"), contents)
+    }
+
+    /// Notes that `note` was opened at `time`
+    pub fn record(&mut self, root: &str, note: &Note, time: SystemTime) {
+        let path = relative(root, &note.path);
+        self.entries.retain(|(recent, _)| recent != &path);
+        let opened = time
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0);
+        self.entries.insert(0, (path, opened));
+        self.entries.truncate(RECENT_LIMIT);
+    }
+
+    /// The notes in `notes` that were opened lately, most recent first.
+    /// Notes that have since gone are left out.
+    pub fn notes<N: AsRef<Note> + Clone>(&self, root: &str, notes: &[N]) -> Vec<N> {
+        self.entries
+            .iter()
+            .filter_map(|(path, _)| {
+                notes
+                    .iter()
+                    .find(|note| &relative(root, &note.as_ref().path) == path)
+                    .cloned()
+            })
+            .collect()
+    }
+}
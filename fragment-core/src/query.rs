@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use crate::note::Note;
+use crate::tags;
+
+/// Filter keys that mean something whatever is in the notes' front matter
+const BUILT_IN_KEYS: &[&str] = &["tag", "links", "has"];
+
+/// A search box query split into the regex handed to grep and the
+/// `key:value` filters that are checked against each note's metadata first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParsedQuery {
+    pub pattern: String,
+    pub filters: Vec<Filter>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    pub key: String,
+    pub value: String,
+}
+
+impl ParsedQuery {
+    /// Splits out only the built-in `tag:`, `links:` and `has:` filters, for
+    /// when there are no notes at hand to say which other keys are real
+    pub fn parse(query: &str) -> ParsedQuery {
+        ParsedQuery::parse_with_keys(query, |_| false)
+    }
+
+    /// Splits out the built-in filters and those on a key that's in the
+    /// front matter of any of `notes`. Other `word:word`s, like `std::fs` or
+    /// `10:30`, stay in the pattern to be searched for.
+    pub fn parse_for<'a, I>(query: &str, notes: I) -> ParsedQuery
+    where
+        I: IntoIterator<Item = &'a Note>,
+    {
+        let mut keys = HashSet::new();
+        for note in notes {
+            keys.extend(note.front_matter.fields.keys().cloned());
+        }
+        ParsedQuery::parse_with_keys(query, |key| keys.contains(key))
+    }
+
+    fn parse_with_keys<F>(query: &str, is_front_matter_key: F) -> ParsedQuery
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut pattern = vec![];
+        let mut filters = vec![];
+
+        for word in words(query) {
+            match Filter::parse(word) {
+                Some(filter)
+                    if BUILT_IN_KEYS.contains(&filter.key.as_str())
+                        || is_front_matter_key(&filter.key) =>
+                {
+                    filters.push(filter)
+                }
+                _ => pattern.push(word),
+            }
+        }
+
+        ParsedQuery {
+            pattern: pattern.join(" "),
+            filters,
+        }
+    }
+
+    pub fn matches(&self, item: &Note) -> bool {
+        self.filters.iter().all(|filter| filter.matches(item))
+    }
+}
+
+/// Splits a query on whitespace, keeping `"quoted phrases"` together so
+/// filters like `links:"Note Title"` can hold spaces.
+fn words(query: &str) -> Vec<&str> {
+    let mut words = vec![];
+    let mut start = None;
+    let mut in_quotes = false;
+
+    for (i, c) in query.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+        if c.is_whitespace() && !in_quotes {
+            if let Some(s) = start.take() {
+                words.push(&query[s..i]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        words.push(&query[s..]);
+    }
+
+    words
+}
+
+impl Filter {
+    /// Reads a `key:value` word. Words that only look like filters, such as
+    /// `http://`, `std::fs`, `10:30` or a bare `key:`, are left in the pattern.
+    fn parse(word: &str) -> Option<Filter> {
+        let colon = word.find(':')?;
+        let (key, value) = (&word[..colon], &word[colon + 1..]);
+        let is_key = !key.is_empty()
+            && !key.chars().all(|c| c.is_ascii_digit())
+            && key
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+        if !is_key || value.is_empty() || value.starts_with('/') || value.starts_with(':') {
+            return None;
+        }
+        Some(Filter {
+            key: key.to_lowercase(),
+            value: value.trim_matches('"').to_lowercase(),
+        })
+    }
+
+    /// `tag:` matches a tag or anything nested under it, `links:` matches
+    /// notes with a `[[link]]` to the given title. Any other key is
+    /// looked up in the front matter, matching when any value of the field
+    /// contains the filter's value, ignoring case.
+    pub fn matches(&self, item: &Note) -> bool {
+        match self.key.as_str() {
+            "tag" => item
+                .tags
+                .iter()
+                .any(|tag| tags::is_within(tag, self.value.trim_start_matches('#'))),
+            "links" => item
+                .links
+                .iter()
+                .any(|link| link.to_lowercase() == self.value),
+            // `has:todo` for notes with open tasks, `has:done` for ticked ones
+            "has" => match self.value.as_str() {
+                "todo" | "todos" => item.tasks.iter().any(|task| !task.done),
+                "done" => item.tasks.iter().any(|task| task.done),
+                _ => false,
+            },
+            _ => item
+                .front_matter
+                .get_all(&self.key)
+                .iter()
+                .any(|value| value.to_lowercase().contains(&self.value)),
+        }
+    }
+}
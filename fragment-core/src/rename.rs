@@ -0,0 +1,374 @@
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::format;
+use crate::links;
+use crate::note::Note;
+
+#[derive(Debug)]
+pub enum RenameError {
+    EmptyTitle,
+    /// The new name would move the note out of its folder
+    OtherFolder,
+    /// The destination isn't inside the notes root
+    OutsideRoot,
+    AlreadyExists(PathBuf),
+    /// A file was edited after the rename was planned
+    Changed(PathBuf),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenameError::EmptyTitle => write!(f, "The new name is empty"),
+            RenameError::OtherFolder => write!(f, "Renaming can't move a note to another folder"),
+            RenameError::OutsideRoot => write!(f, "Notes can't be moved out of the notes folder"),
+            RenameError::AlreadyExists(path) => write!(f, "{} already exists", path.display()),
+            RenameError::Changed(path) => {
+                write!(f, "{} changed on disk in the meantime", path.display())
+            }
+            RenameError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// A note's new contents, along with what was there before so it can be put back
+#[derive(Clone, Debug)]
+pub struct FileEdit {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+/// Everything a rename touches: the move itself and the link rewrites in the
+/// notes referring to it. Plans are worked out up front so the affected files
+/// can be shown before anything is changed, and inverted to undo.
+#[derive(Clone, Debug)]
+pub struct RenamePlan {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub edits: Vec<FileEdit>,
+}
+
+/// Notes holding a wiki-link to `note`, found from the index without reading
+/// any files. Markdown links are only found once the full plan is made.
+pub fn referrers<N: AsRef<Note> + Clone>(note: &Note, notes: &[N]) -> Vec<N> {
+    notes
+        .iter()
+        .filter(|other| other.as_ref().path != note.path)
+        .filter(|other| {
+            other
+                .as_ref()
+                .links
+                .iter()
+                .any(|link| is_old_name(link, note))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Wiki-links are only rewritten when they use the note's title or file name.
+/// Links through an alias keep working after a rename, so they're left alone.
+fn is_old_name(target: &str, note: &Note) -> bool {
+    let target = target.to_lowercase();
+    target == note.title.to_lowercase() || Some(target) == stem(Path::new(note.path.as_ref()))
+}
+
+/// A note being renamed, as the formats see it when rewriting links to it
+pub struct Renaming<'a> {
+    pub note: &'a Note,
+    pub new_title: &'a str,
+    pub from: &'a Path,
+    pub to: &'a Path,
+}
+
+impl Renaming<'_> {
+    /// Whether a link naming `target` is to the note by its old name
+    pub fn is_old_name(&self, target: &str) -> bool {
+        is_old_name(target, self.note)
+    }
+
+    /// Whether `target`, a path relative to the note at `referrer`, is the
+    /// renamed note's file
+    pub fn is_file(&self, referrer: &Path, target: &str) -> bool {
+        let dir = referrer.parent().unwrap_or_else(|| Path::new(""));
+        normalize(&dir.join(target)) == normalize(self.from)
+    }
+
+    pub fn new_file_name(&self) -> String {
+        self.to
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+fn stem(path: &Path) -> Option<String> {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+}
+
+pub fn plan<N: AsRef<Note>>(
+    root: &Path,
+    note: &Note,
+    new_title: &str,
+    notes: &[N],
+) -> Result<RenamePlan, RenameError> {
+    let new_title = new_title.trim();
+    if new_title.is_empty() {
+        return Err(RenameError::EmptyTitle);
+    }
+
+    let from = PathBuf::from(note.path.as_ref());
+    let to = normalize(&from.with_file_name(with_extension_of(new_title, &from)));
+    if !to.starts_with(normalize(root)) || to.parent() != from.parent().map(normalize).as_deref() {
+        return Err(RenameError::OtherFolder);
+    }
+    if to.exists() {
+        return Err(RenameError::AlreadyExists(to));
+    }
+
+    let renaming = Renaming {
+        note,
+        new_title,
+        from: &from,
+        to: &to,
+    };
+    let mut edits = vec![];
+    for other in notes {
+        let path = PathBuf::from(other.as_ref().path.as_ref());
+        let before = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            // Binary files can't link anywhere
+            Err(_) => continue,
+        };
+        let after = format::for_path(&path).rewrite_links(&before, &path, &renaming);
+        if after != before {
+            edits.push(FileEdit {
+                path,
+                before,
+                after,
+            });
+        }
+    }
+
+    Ok(RenamePlan { from, to, edits })
+}
+
+impl RenamePlan {
+    /// The plan that puts everything back the way it was
+    pub fn inverse(&self) -> RenamePlan {
+        RenamePlan {
+            from: self.to.clone(),
+            to: self.from.clone(),
+            edits: self
+                .edits
+                .iter()
+                .map(|edit| FileEdit {
+                    path: self.moved(&edit.path).to_path_buf(),
+                    before: edit.after.clone(),
+                    after: edit.before.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Moves the note and then rewrites the referring notes. Nothing is
+    /// touched unless every file is still exactly as it was when the plan was
+    /// made, and if a rewrite fails everything done so far is put back.
+    pub fn apply(&self) -> Result<(), RenameError> {
+        if self.to.exists() {
+            return Err(RenameError::AlreadyExists(self.to.clone()));
+        }
+        for edit in &self.edits {
+            let current = std::fs::read_to_string(&edit.path).map_err(RenameError::Io)?;
+            if current != edit.before {
+                return Err(RenameError::Changed(edit.path.clone()));
+            }
+        }
+
+        std::fs::rename(&self.from, &self.to).map_err(RenameError::Io)?;
+        for (i, edit) in self.edits.iter().enumerate() {
+            if let Err(err) = std::fs::write(self.moved(&edit.path), &edit.after) {
+                for done in &self.edits[..i] {
+                    let _ = std::fs::write(self.moved(&done.path), &done.before);
+                }
+                let _ = std::fs::rename(&self.to, &self.from);
+                return Err(RenameError::Io(err));
+            }
+        }
+        Ok(())
+    }
+
+    /// Where the file at `path` is once the note has been moved
+    fn moved<'a>(&'a self, path: &'a Path) -> &'a Path {
+        if path == self.from {
+            &self.to
+        } else {
+            path
+        }
+    }
+}
+
+/// Moves a note into `folder`, creating the folder if need be. A file name
+/// that's already taken there gets a number added rather than being
+/// overwritten. Returns where the note ended up.
+pub fn move_into(root: &Path, path: &Path, folder: &Path) -> Result<PathBuf, RenameError> {
+    let folder = normalize(folder);
+    if !folder.starts_with(normalize(root)) {
+        return Err(RenameError::OutsideRoot);
+    }
+    std::fs::create_dir_all(&folder).map_err(RenameError::Io)?;
+    let to = unique_path(&folder, path);
+    std::fs::rename(path, &to).map_err(RenameError::Io)?;
+    Ok(to)
+}
+
+/// Moves a note back to where it was, as long as nothing has taken its place
+pub fn move_back(from: &Path, to: &Path) -> Result<(), RenameError> {
+    if to.exists() {
+        return Err(RenameError::AlreadyExists(to.to_path_buf()));
+    }
+    std::fs::rename(from, to).map_err(RenameError::Io)
+}
+
+/// Copies a note next to itself as `Name copy.md`, returning the copy's path
+pub fn duplicate(path: &Path) -> Result<PathBuf, RenameError> {
+    let copy_name = with_extension_of(&format!("{} copy", stem_of(path)), path);
+    let folder = path.parent().unwrap_or_else(|| Path::new(""));
+    let to = unique_path(folder, &copy_name);
+    std::fs::copy(path, &to).map_err(RenameError::Io)?;
+    Ok(to)
+}
+
+/// `folder/name`, or `folder/name 2`, `folder/name 3`... if that's taken
+fn unique_path(folder: &Path, path: &Path) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default();
+    let mut candidate = folder.join(file_name);
+    let mut n = 2;
+    while candidate.exists() {
+        let name = with_extension_of(&format!("{} {}", stem_of(path), n), path);
+        candidate = folder.join(name);
+        n += 1;
+    }
+    candidate
+}
+
+/// `name` with `path`'s extension added on
+fn with_extension_of(name: &str, path: &Path) -> PathBuf {
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    with_extension(name, &extension)
+}
+
+/// `name` with `extension` added on. Dots already in `name` are part of it,
+/// so `Meeting 2024.03.01` becomes `Meeting 2024.03.01.md`.
+pub(crate) fn with_extension(name: &str, extension: &str) -> PathBuf {
+    if extension.is_empty() {
+        PathBuf::from(name)
+    } else {
+        PathBuf::from(format!("{}.{}", name, extension))
+    }
+}
+
+fn stem_of(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Rewrites `[[Old Name]]`, `[[Old Name|label]]` and `[[Old Name#heading]]`
+/// to use the new name
+pub(crate) fn rewrite_wiki_links(contents: &str, renaming: &Renaming) -> String {
+    let mut out = String::with_capacity(contents.len());
+    let mut rest = contents;
+
+    while let Some(start) = rest.find("[[") {
+        out.push_str(&rest[..start + 2]);
+        rest = &rest[start + 2..];
+        let end = match rest.find("]]") {
+            Some(end) => end,
+            None => break,
+        };
+        let inner = &rest[..end];
+        if renaming.is_old_name(links::link_target(inner)) {
+            // Keep any `|label` or `#heading` after the target
+            let suffix = inner
+                .find(|c| c == '|' || c == '#')
+                .map(|i| &inner[i..])
+                .unwrap_or("");
+            out.push_str(renaming.new_title);
+            out.push_str(suffix);
+        } else {
+            out.push_str(inner);
+        }
+        rest = &rest[end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Rewrites `[text](relative/path.md)` links in the note at `referrer` that
+/// point at the renamed note so they point at its new file instead.
+pub(crate) fn rewrite_markdown_links(
+    contents: &str,
+    referrer: &Path,
+    renaming: &Renaming,
+) -> String {
+    let new_name = renaming.new_file_name();
+
+    let mut out = String::with_capacity(contents.len());
+    let mut rest = contents;
+
+    while let Some(start) = rest.find("](") {
+        out.push_str(&rest[..start + 2]);
+        rest = &rest[start + 2..];
+        let end = match rest.find(|c| c == ')' || c == '\n') {
+            Some(end) => end,
+            None => break,
+        };
+        let link = &rest[..end];
+        let (target, anchor) = match link.find('#') {
+            Some(i) => (&link[..i], &link[i..]),
+            None => (link, ""),
+        };
+
+        let is_relative = !target.is_empty() && !target.contains("://") && !target.starts_with('/');
+        let decoded = target.replace("%20", " ");
+        if is_relative && renaming.is_file(referrer, &decoded) {
+            let name = if target.contains("%20") {
+                new_name.replace(' ', "%20")
+            } else {
+                new_name.clone()
+            };
+            if let Some(slash) = target.rfind('/') {
+                out.push_str(&target[..=slash]);
+            }
+            out.push_str(&name);
+            out.push_str(anchor);
+        } else {
+            out.push_str(link);
+        }
+        rest = &rest[end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Resolves `.` and `..` without touching the file system, since the paths
+/// being compared may not exist yet.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,153 @@
+//! Named searches, kept in `.fragment/searches.toml` in the notes root as
+//! one `"Name" = "query"` line each, in the order they're shown.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicU64;
+use std::sync::Mutex;
+
+use crate::index::Index;
+use crate::note::Note;
+use crate::query::ParsedQuery;
+use crate::scope::Scope;
+use crate::search::{self, SortMethod};
+use crate::title::TitleStrategy;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedSearch {
+    pub name: String,
+    /// In the search box's syntax
+    pub query: String,
+}
+
+fn file(root: &str) -> PathBuf {
+    Path::new(root).join(".fragment").join("searches.toml")
+}
+
+/// The saved searches for `root`, or none if there's no file yet
+pub fn load(root: &str) -> Vec<SavedSearch> {
+    match std::fs::read_to_string(file(root)) {
+        Ok(contents) => parse(&contents),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn save(root: &str, searches: &[SavedSearch]) -> io::Result<()> {
+    let path = file(root);
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder)?;
+    }
+    let mut contents = String::new();
+    for saved in searches {
+        contents.push_str(&format!(
+            "{} = {}\n",
+            quote(&saved.name),
+            quote(&saved.query)
+        ));
+    }
+    std::fs::write(path, contents)
+}
+
+fn parse(contents: &str) -> Vec<SavedSearch> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (name, rest) = unquote(line)?;
+            let rest = rest.trim_start().strip_prefix('=')?;
+            let (query, _) = unquote(rest.trim_start())?;
+            Some(SavedSearch { name, query })
+        })
+        .collect()
+}
+
+/// `text` as a TOML basic string
+pub(crate) fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Reads a TOML basic string off the front of `text`, returning it and the
+/// rest
+pub(crate) fn unquote(text: &str) -> Option<(String, &str)> {
+    let mut chars = text.strip_prefix('"')?.char_indices();
+    let mut unquoted = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((unquoted, &text[i + 2..])),
+            '\\' => unquoted.push(match chars.next()?.1 {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'u' => unicode_escape(&mut chars, 4)?,
+                'U' => unicode_escape(&mut chars, 8)?,
+                c => c,
+            }),
+            c => unquoted.push(c),
+        }
+    }
+    None
+}
+
+fn unicode_escape(chars: &mut std::str::CharIndices, digits: usize) -> Option<char> {
+    let hex: String = chars.take(digits).map(|(_, c)| c).collect();
+    if hex.len() != digits {
+        return None;
+    }
+    std::char::from_u32(u32::from_str_radix(&hex, 16).ok()?)
+}
+
+/// How many notes under `root` match each saved search, or `None` for one
+/// whose query is invalid. The folder is walked once for all of them, and
+/// only the notes that changed since `index` last saw them are read.
+pub fn counts(
+    root: &str,
+    titles: TitleStrategy,
+    index: &Mutex<Index>,
+    searches: &[SavedSearch],
+) -> Vec<Option<usize>> {
+    let notes = search::list_files_in(
+        root,
+        &Scope::default(),
+        SortMethod::DateNewest,
+        titles,
+        &mut index.lock().unwrap(),
+        &AtomicU64::new(0),
+        1,
+    );
+    counts_in(&notes, searches)
+}
+
+/// How many of `notes` match each saved search
+pub fn counts_in<N: AsRef<Note>>(notes: &[N], searches: &[SavedSearch]) -> Vec<Option<usize>> {
+    searches
+        .iter()
+        .map(|saved| {
+            let query = ParsedQuery::parse_for(&saved.query, notes.iter().map(AsRef::as_ref));
+            let filtered: Vec<Note> = notes
+                .iter()
+                .map(AsRef::as_ref)
+                .filter(|note| query.matches(note))
+                .cloned()
+                .collect();
+            search::grep_life(&query.pattern, &filtered, &AtomicU64::new(0), 1)
+                .ok()
+                .map(|matches| matches.len())
+        })
+        .collect()
+}
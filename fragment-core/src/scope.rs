@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, NaiveDate};
+
+/// Limits on which files a search looks at. They're checked during the walk,
+/// before a file is read, so everything outside them is skipped entirely.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scope {
+    /// Folder under the root to search, or the whole root
+    pub folder: Option<String>,
+    /// Extensions to include, without the dot. Empty means any.
+    pub extensions: Vec<String>,
+    /// Only files last modified on or after this day
+    pub modified_after: Option<NaiveDate>,
+    /// Only files last modified on or before this day
+    pub modified_before: Option<NaiveDate>,
+}
+
+impl Scope {
+    /// Where the walk starts for this scope
+    pub fn start(&self, root: &str) -> PathBuf {
+        match &self.folder {
+            Some(folder) => Path::new(root).join(folder),
+            None => PathBuf::from(root),
+        }
+    }
+
+    /// Whether the file at `path` under `root` is in the folder and has one
+    /// of the extensions
+    pub fn includes_path(&self, root: &str, path: &Path) -> bool {
+        if !path.starts_with(self.start(root)) {
+            return false;
+        }
+        if self.extensions.is_empty() {
+            return true;
+        }
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.extensions
+            .iter()
+            .any(|wanted| wanted.to_lowercase() == extension)
+    }
+
+    /// Whether a file last modified at `modified` is in the date range
+    pub fn includes_modified(&self, modified: SystemTime) -> bool {
+        let day = DateTime::<Local>::from(modified).naive_local().date();
+        self.modified_after.map_or(true, |after| day >= after)
+            && self.modified_before.map_or(true, |before| day <= before)
+    }
+}
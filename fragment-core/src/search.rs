@@ -0,0 +1,495 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use grep::matcher::Matcher;
+use grep::regex::RegexMatcher;
+use grep::searcher::sinks::UTF8;
+use grep::searcher::{BinaryDetection, SearcherBuilder};
+
+use walkdir::{DirEntry, WalkDir};
+
+use chrono::{Local, NaiveDate, TimeZone};
+
+use crate::format;
+use crate::frontmatter::FrontMatter;
+use crate::index::Index;
+use crate::note::Note;
+use crate::query::ParsedQuery;
+use crate::scope::Scope;
+use crate::tasks;
+use crate::title::TitleStrategy;
+use crate::watch::Batch;
+
+/// The order notes are listed in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortMethod {
+    DateNewest,
+    DateOldest,
+    TitleAZ,
+    TitleZA,
+    // NoSort,
+}
+
+impl SortMethod {
+    pub const ALL: [SortMethod; 4] = [
+        SortMethod::DateNewest,
+        SortMethod::DateOldest,
+        SortMethod::TitleAZ,
+        SortMethod::TitleZA,
+    ];
+
+    /// The name it's written as in settings files
+    pub fn name(self) -> &'static str {
+        match self {
+            SortMethod::DateNewest => "newest",
+            SortMethod::DateOldest => "oldest",
+            SortMethod::TitleAZ => "title",
+            SortMethod::TitleZA => "title-reversed",
+        }
+    }
+}
+
+impl Default for SortMethod {
+    fn default() -> Self {
+        SortMethod::DateNewest
+    }
+}
+
+impl FromStr for SortMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SortMethod::ALL
+            .iter()
+            .copied()
+            .find(|method| method.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown sort order '{}', expected one of: newest, oldest, title, title-reversed",
+                    s
+                )
+            })
+    }
+}
+
+/// Puts `list` in `sort_by` order. Titles are compared ignoring case.
+pub fn sort<N: AsRef<Note>>(list: &mut [N], sort_by: SortMethod) {
+    let title = |note: &N| note.as_ref().title.to_lowercase();
+    match sort_by {
+        SortMethod::DateNewest => list.sort_by_key(|note| Reverse(note.as_ref().modified)),
+        SortMethod::DateOldest => list.sort_by_key(|note| note.as_ref().modified),
+        SortMethod::TitleAZ => list.sort_by_cached_key(title),
+        SortMethod::TitleZA => {
+            list.sort_by_cached_key(title);
+            list.reverse();
+        }
+    }
+}
+
+/// Every file and folder under `root`, leaving out hidden ones like `.git`,
+/// `.trash` and `.fragment` along with everything inside them. The root is
+/// walked even when it's hidden itself.
+pub fn walk(root: &str) -> impl Iterator<Item = walkdir::Result<DirEntry>> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !is_hidden(e))
+}
+
+/// The matcher for the regex part of a query
+pub fn matcher(pattern: &str) -> Result<RegexMatcher, grep::regex::Error> {
+    RegexMatcher::new(pattern)
+}
+
+/// A searcher that gives up on binary files at their first NUL byte
+pub fn searcher_builder() -> SearcherBuilder {
+    let mut builder = SearcherBuilder::new();
+    builder.binary_detection(BinaryDetection::quit(b'\x00'));
+    builder
+}
+
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .map(|s| s.starts_with("."))
+        .unwrap_or(false)
+}
+
+/// Whether the walk skips `relative`, a path relative to the root, because
+/// it is or is inside something hidden
+pub fn is_hidden_path(relative: &Path) -> bool {
+    relative.components().any(|part| {
+        part.as_os_str()
+            .to_str()
+            .map(|s| s.starts_with("."))
+            .unwrap_or(false)
+    })
+}
+
+/// A folder under the root, and how many notes are in it and its subfolders
+#[derive(Clone, Debug, PartialEq)]
+pub struct Folder {
+    /// Path relative to the root, empty for the root itself
+    pub path: String,
+    pub notes: usize,
+}
+
+/// Every folder the walk goes into, starting with the root itself, sorted so
+/// parents come right before their children. Only file names are looked at,
+/// so this is much quicker than listing the notes.
+pub fn folders(root: &str) -> Vec<Folder> {
+    let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+    counts.insert(PathBuf::new(), 0);
+    for entry in walk(root).filter_map(Result::ok) {
+        let relative = match entry.path().strip_prefix(root) {
+            Ok(relative) if entry.depth() > 0 => relative,
+            _ => continue,
+        };
+        if entry.file_type().is_dir() {
+            counts.entry(relative.to_path_buf()).or_insert(0);
+        } else if let Some(parent) = relative.parent() {
+            for folder in parent.ancestors() {
+                *counts.entry(folder.to_path_buf()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut folders: Vec<(PathBuf, usize)> = counts.into_iter().collect();
+    // Paths sort by component, which keeps `a/b` right after `a`
+    folders.sort();
+    folders
+        .into_iter()
+        .map(|(path, notes)| Folder {
+            path: path.display().to_string(),
+            notes,
+        })
+        .collect()
+}
+
+/// Everything one search pass produces: the notes matching the query, plus
+/// every note found on the walk for the views that summarise the whole root.
+#[derive(Clone, Debug)]
+pub struct SearchResults {
+    pub matches: Vec<Note>,
+    pub notes: Vec<Note>,
+}
+
+/// Finds the notes under `dir` matching `query`, in the search box's syntax.
+/// Searches are numbered so a newer one can cancel older ones: each bumps
+/// `sequence_ref`, and gives up early once it's moved past `self_sequence`.
+/// One-off searches can pass a fresh counter and 1.
+pub fn search(
+    query: &str,
+    dir: &str,
+    titles: TitleStrategy,
+    sequence_ref: &AtomicU64,
+    self_sequence: u64,
+) -> Result<SearchResults, Box<dyn Error>> {
+    search_in(
+        query,
+        dir,
+        &Scope::default(),
+        titles,
+        &Mutex::new(Index::new()),
+        sequence_ref,
+        self_sequence,
+    )
+}
+
+/// Like [`search`], only looking at the files in `scope`, and reading the
+/// ones that haven't changed from `index`. The index is locked for the walk,
+/// after this search has told older ones to give up.
+pub fn search_in(
+    query: &str,
+    dir: &str,
+    scope: &Scope,
+    titles: TitleStrategy,
+    index: &Mutex<Index>,
+    sequence_ref: &AtomicU64,
+    self_sequence: u64,
+) -> Result<SearchResults, Box<dyn Error>> {
+    sequence_ref.fetch_add(1, Ordering::SeqCst);
+
+    let files = list_files_in(
+        dir,
+        scope,
+        SortMethod::DateNewest,
+        titles,
+        &mut index.lock().unwrap(),
+        sequence_ref,
+        self_sequence,
+    );
+
+    let query = ParsedQuery::parse_for(query, &files);
+    let filtered: Vec<Note> = files
+        .iter()
+        .filter(|file| query.matches(file))
+        .cloned()
+        .collect();
+
+    Ok(SearchResults {
+        matches: grep_life(&query.pattern, &filtered, sequence_ref, self_sequence)?,
+        notes: files,
+    })
+}
+
+/// Extensions of files that are never notes, so they're listed without
+/// being read
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "tiff", "heic", "pdf", "zip", "gz", "tgz",
+    "bz2", "xz", "7z", "rar", "mp3", "mp4", "m4a", "mov", "avi", "mkv", "wav", "flac", "ogg",
+    "exe", "dll", "so", "dylib", "o", "a", "class", "jar", "wasm", "sqlite", "db", "ttf", "otf",
+    "woff", "woff2", "psd", "docx", "xlsx", "pptx", "odt",
+];
+
+/// The contents of the note at `path`, or `None` if it's not text. Like the
+/// grep searcher, a NUL byte is taken to mean a binary file.
+fn read_contents(path: &Path) -> Option<String> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    if extension.map_or(false, |extension| {
+        BINARY_EXTENSIONS.contains(&extension.as_str())
+    }) {
+        return None;
+    }
+    match std::fs::read(path) {
+        Ok(bytes) if bytes.contains(&0) => None,
+        Ok(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(_e) => {
+            eprintln!("Couldn't read file: {}", path.display());
+            Some(String::new())
+        }
+    }
+}
+
+/// Front matter `created:` dates win over the file system's, which are lost
+/// whenever notes get copied or synced around.
+fn front_matter_created(front_matter: &FrontMatter) -> Option<SystemTime> {
+    let created = front_matter.get("created")?;
+    let date = NaiveDate::parse_from_str(created.get(..10)?, "%Y-%m-%d").ok()?;
+    Local
+        .from_local_datetime(&date.and_hms(0, 0, 0))
+        .earliest()
+        .map(SystemTime::from)
+}
+
+fn as_list(values: &[String]) -> Vec<Arc<str>> {
+    values.iter().map(|value| value.as_str().into()).collect()
+}
+
+pub(crate) fn relative_folder(root: &Path, path: &Path) -> String {
+    path.parent()
+        .and_then(|parent| parent.strip_prefix(root).ok())
+        .map(|folder| folder.display().to_string())
+        .unwrap_or_default()
+}
+
+pub fn list_of_all_files(
+    root: &str,
+    sort_by: SortMethod,
+    titles: TitleStrategy,
+    sequence_ref: &AtomicU64,
+    self_sequence: u64,
+) -> Vec<Note> {
+    list_files_in(
+        root,
+        &Scope::default(),
+        sort_by,
+        titles,
+        &mut Index::new(),
+        sequence_ref,
+        self_sequence,
+    )
+}
+
+/// The notes under `root` that are in `scope`. Folders outside it aren't
+/// walked, and files outside it or unchanged in `index` aren't read.
+pub fn list_files_in(
+    root: &str,
+    scope: &Scope,
+    sort_by: SortMethod,
+    titles: TitleStrategy,
+    index: &mut Index,
+    sequence_ref: &AtomicU64,
+    self_sequence: u64,
+) -> Vec<Note> {
+    let mut list = Vec::new();
+
+    for result in walk(&scope.start(root).display().to_string()) {
+        if sequence_ref.load(Ordering::SeqCst) > self_sequence {
+            break;
+        }
+
+        match result {
+            Ok(entry) => {
+                let in_scope = scope.includes_path(root, entry.path())
+                    && entry
+                        .metadata()
+                        .ok()
+                        .and_then(|metadata| metadata.modified().ok())
+                        .map_or(true, |modified| scope.includes_modified(modified));
+                if entry.file_type().is_file() && in_scope {
+                    if let Some(note) = index.read_note(root, entry.path(), titles) {
+                        list.push(note)
+                    }
+                }
+            }
+            Err(err) => eprintln!("WALKDIR ERROR: {}", err),
+        }
+    }
+
+    sort(&mut list, sort_by);
+    list
+}
+
+/// Reads and indexes the file at `path` under `root`, or `None` if it's
+/// gone by the time we get to it. Files that aren't text are listed without
+/// their contents being indexed.
+pub fn read_note(root: &str, path: &Path, titles: TitleStrategy) -> Option<Note> {
+    let metadata = path.metadata().ok()?;
+    let modified = metadata
+        .modified()
+        .expect("What to do if this doesn't work?");
+    let folder: Arc<str> = relative_folder(Path::new(root), path).into();
+
+    let contents = read_contents(path);
+    let format = format::for_path(path);
+    let (front_matter, body) = match &contents {
+        Some(contents) => format.split_metadata(contents),
+        None => (FrontMatter::default(), ""),
+    };
+    Some(Note {
+        file_name: path.file_name()?.to_string_lossy().into(),
+        modified,
+        created: front_matter_created(&front_matter).or_else(|| metadata.created().ok()),
+        folder,
+        size: metadata.len(),
+        word_count: body.split_whitespace().count(),
+        tags: Arc::new(format.tags(&front_matter, body)),
+        aliases: Arc::new(as_list(front_matter.get_all("aliases"))),
+        links: Arc::new(format.links(body)),
+        tasks: Arc::new(contents.as_deref().map(tasks::extract).unwrap_or_default()),
+        title: titles.title(path, format, &front_matter, body).into(),
+        front_matter: Arc::new(front_matter),
+        found_line: None,
+        found_at: None,
+        path: path.display().to_string().into(),
+    })
+}
+
+pub fn grep_life(
+    pattern: &str,
+    files: &Vec<Note>,
+    sequence_ref: &AtomicU64,
+    self_sequence: u64,
+) -> Result<Vec<Note>, Box<dyn Error>> {
+    let mut matches: Vec<Note> = vec![];
+    let matcher = matcher(pattern)?;
+    let mut searcher = searcher_builder().build();
+
+    for file in files {
+        if sequence_ref.load(Ordering::SeqCst) > self_sequence {
+            break;
+        }
+        let mut found = false;
+        let result = searcher.search_path(
+            &matcher,
+            &file.path.to_string(),
+            UTF8(|ln, line| {
+                let col = match matcher.find(line.as_bytes()) {
+                    Ok(Some(m)) => line[..m.start()].chars().count() as u64 + 1,
+                    _ => 1,
+                };
+                matches.push(Note {
+                    found_line: Some(format!("{}: {}", ln, line.trim()).into()),
+                    found_at: Some((ln, col)),
+                    ..file.clone()
+                });
+                found = true;
+                //we stop searching after our first find by returning false
+                Ok(false)
+            }),
+        );
+        if let Err(err) = result {
+            eprintln!("GREP ERROR: {}: {}", file.path, err);
+        }
+        // Titles can come from the file name, so they're searched on their own too
+        if !found && matcher.is_match(file.title.as_bytes())? {
+            matches.push(file.clone());
+        }
+    }
+
+    Ok(matches)
+}
+
+/// Brings a search's results up to date with a batch of file changes,
+/// re-reading and re-grepping only the notes the batch touched that are in
+/// `scope`. Notes the batch didn't touch are kept as they are, in both lists.
+/// All notes come back newest first, like a full search lists them, but the
+/// matches are left for the caller to sort however the results are shown.
+pub fn apply_changes<N>(
+    query: &str,
+    root: &str,
+    scope: &Scope,
+    titles: TitleStrategy,
+    batch: &Batch,
+    notes: &[N],
+    matches: &[N],
+) -> Result<(Vec<N>, Vec<N>), Box<dyn Error>>
+where
+    N: AsRef<Note> + From<Note> + Clone,
+{
+    let mut fresh = Vec::new();
+    for path in &batch.changed {
+        if path.is_dir() {
+            for entry in walk(&path.display().to_string()) {
+                match entry {
+                    Ok(entry) if entry.file_type().is_file() => {
+                        fresh.extend(read_note(root, entry.path(), titles))
+                    }
+                    Ok(_) => {}
+                    Err(err) => eprintln!("WALKDIR ERROR: {}", err),
+                }
+            }
+        } else {
+            fresh.extend(read_note(root, path, titles));
+        }
+    }
+
+    fresh.retain(|note| {
+        scope.includes_path(root, Path::new(note.path.as_ref()))
+            && scope.includes_modified(note.modified)
+    });
+    // A new folder and the files in it can both be in the batch
+    fresh.sort_by(|a, b| a.path.cmp(&b.path));
+    fresh.dedup_by(|a, b| a.path == b.path);
+
+    let query = ParsedQuery::parse_for(query, notes.iter().map(AsRef::as_ref).chain(&fresh));
+    let candidates: Vec<Note> = fresh
+        .iter()
+        .filter(|note| query.matches(note))
+        .cloned()
+        .collect();
+    let fresh_matches = grep_life(&query.pattern, &candidates, &AtomicU64::new(0), 1)?;
+
+    let untouched = |list: &[N]| -> Vec<N> {
+        list.iter()
+            .filter(|note| !batch.affects(Path::new(note.as_ref().path.as_ref())))
+            .cloned()
+            .collect()
+    };
+    let mut notes = untouched(notes);
+    notes.extend(fresh.into_iter().map(N::from));
+    let mut matches = untouched(matches);
+    matches.extend(fresh_matches.into_iter().map(N::from));
+
+    sort(&mut notes, SortMethod::DateNewest);
+    Ok((notes, matches))
+}
@@ -0,0 +1,118 @@
+//! How the app was left when it was last closed, kept in
+//! `.fragment/state.toml` in the notes root so it opens the same way next
+//! time. It's rewritten on every exit, as flat `key = value` lines.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::saved::{quote, unquote};
+use crate::search::SortMethod;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+    /// Width and height of the window
+    pub window_size: Option<(f64, f64)>,
+    /// Where the window's top left corner was on screen
+    pub window_position: Option<(f64, f64)>,
+    /// How much of the height the results get, with the preview below
+    pub split_point: f64,
+    pub sort: SortMethod,
+    pub query: String,
+    /// Path of the note that was selected
+    pub selected: Option<String>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            window_size: None,
+            window_position: None,
+            split_point: 0.8,
+            sort: SortMethod::default(),
+            query: String::new(),
+            selected: None,
+        }
+    }
+}
+
+fn file(root: &str) -> PathBuf {
+    Path::new(root).join(".fragment").join("state.toml")
+}
+
+impl Session {
+    /// The session last saved for `root`, or a fresh one
+    pub fn load(root: &str) -> Session {
+        match std::fs::read_to_string(file(root)) {
+            Ok(contents) => Session::parse(&contents),
+            Err(_) => Session::default(),
+        }
+    }
+
+    pub fn save(&self, root: &str) -> io::Result<()> {
+        let path = file(root);
+        if let Some(folder) = path.parent() {
+            std::fs::create_dir_all(folder)?;
+        }
+        let mut contents = String::new();
+        if let Some((width, height)) = self.window_size {
+            contents.push_str(&format!("window_size = [{}, {}]\n", width, height));
+        }
+        if let Some((x, y)) = self.window_position {
+            contents.push_str(&format!("window_position = [{}, {}]\n", x, y));
+        }
+        contents.push_str(&format!("split_point = {}\n", self.split_point));
+        contents.push_str(&format!("sort = {}\n", quote(self.sort.name())));
+        contents.push_str(&format!("query = {}\n", quote(&self.query)));
+        if let Some(selected) = &self.selected {
+            contents.push_str(&format!("selected = {}\n", quote(selected)));
+        }
+        std::fs::write(path, contents)
+    }
+
+    fn parse(contents: &str) -> Session {
+        let mut session = Session::default();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            let equals = match line.find('=') {
+                Some(equals) => equals,
+                None => continue,
+            };
+            let key = line[..equals].trim();
+            let value = line[equals + 1..].trim();
+            let text = || unquote(value).map(|(text, _)| text);
+
+            match key {
+                "window_size" => session.window_size = pair(value),
+                "window_position" => session.window_position = pair(value),
+                "split_point" => {
+                    if let Ok(split_point) = value.parse::<f64>() {
+                        if split_point > 0.0 && split_point < 1.0 {
+                            session.split_point = split_point;
+                        }
+                    }
+                }
+                "sort" => {
+                    if let Some(sort) = text().and_then(|name| name.parse().ok()) {
+                        session.sort = sort;
+                    }
+                }
+                "query" => session.query = text().unwrap_or_default(),
+                "selected" => session.selected = text(),
+                _ => {}
+            }
+        }
+
+        session
+    }
+}
+
+/// Reads `[a, b]`
+fn pair(value: &str) -> Option<(f64, f64)> {
+    let value = value.strip_prefix('[')?.strip_suffix(']')?;
+    let mut numbers = value.split(',').map(|number| number.trim().parse::<f64>());
+    match (numbers.next()?, numbers.next()?, numbers.next()) {
+        (Ok(a), Ok(b), None) => Some((a, b)),
+        _ => None,
+    }
+}
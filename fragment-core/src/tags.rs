@@ -0,0 +1,87 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use crate::frontmatter::FrontMatter;
+
+/// Tags for a note: inline `#tags` from the body followed by the front
+/// matter's `tags:`, without duplicates. Nested tags keep their full path,
+/// e.g. `project/alpha`.
+pub fn extract(front_matter: &FrontMatter, body: &str) -> Vec<Arc<str>> {
+    let mut tags: Vec<Arc<str>> = vec![];
+    let front_matter_tags = front_matter
+        .get_all("tags")
+        .iter()
+        .map(|tag| tag.trim_start_matches('#'));
+
+    for tag in inline_tags(body).chain(front_matter_tags) {
+        let tag = tag.trim_matches('/');
+        if !tag.is_empty() && !tags.iter().any(|t| t.as_ref() == tag) {
+            tags.push(tag.into());
+        }
+    }
+    tags
+}
+
+fn inline_tags(body: &str) -> impl Iterator<Item = &str> {
+    let mut in_code_block = false;
+    body.lines()
+        .filter(move |line| {
+            // Fenced code is full of `#include`s and shell comments, not tags
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+                return false;
+            }
+            !in_code_block
+        })
+        .flat_map(|line| line.split_whitespace())
+        .filter_map(|word| {
+            if !word.starts_with('#') {
+                return None;
+            }
+            let tag = word[1..].trim_end_matches(|c: char| !is_tag_char(c));
+            // `#` and `##` on their own are headings, `#123` is an issue number
+            let valid = !tag.is_empty()
+                && tag.chars().all(is_tag_char)
+                && tag.chars().any(|c| c.is_alphabetic());
+            if valid {
+                Some(tag)
+            } else {
+                None
+            }
+        })
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '/' || c == '-' || c == '_'
+}
+
+/// Whether `tag` is `parent` or nested anywhere under it, ignoring case
+pub fn is_within(tag: &str, parent: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let parent = parent.to_lowercase();
+    tag == parent || tag.starts_with(&format!("{}/", parent))
+}
+
+/// How many notes carry each tag. A note tagged `project/alpha` counts
+/// towards both `project/alpha` and `project`, so the result reads as a tree
+/// when walked in order.
+pub fn count<'a>(notes: impl Iterator<Item = &'a [Arc<str>]>) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for tags in notes {
+        let mut seen = BTreeSet::new();
+        for tag in tags {
+            let mut prefix = String::new();
+            for part in tag.split('/') {
+                if !prefix.is_empty() {
+                    prefix.push('/');
+                }
+                prefix.push_str(part);
+                seen.insert(prefix.clone());
+            }
+        }
+        for tag in seen {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+    }
+    counts
+}
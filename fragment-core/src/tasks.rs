@@ -0,0 +1,108 @@
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A `- [ ] checkbox` item in a note
+#[derive(Clone, Debug, PartialEq)]
+pub struct Task {
+    /// 1-based line in the note's file
+    pub line: u64,
+    pub text: Arc<str>,
+    pub done: bool,
+}
+
+/// Every checkbox list item in a note, in order. Checkboxes inside fenced
+/// code or org source blocks are examples, not tasks, and are skipped.
+pub fn extract(contents: &str) -> Vec<Task> {
+    let mut tasks = vec![];
+    let mut in_code_block = false;
+
+    for (i, line) in contents.lines().enumerate() {
+        let trimmed = line.trim_start().to_lowercase();
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if trimmed.starts_with("#+begin_") || trimmed.starts_with("#+end_") {
+            in_code_block = trimmed.starts_with("#+begin_");
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        if let Some((_, done, text)) = checkbox(line) {
+            tasks.push(Task {
+                line: i as u64 + 1,
+                text: text.into(),
+                done,
+            });
+        }
+    }
+
+    tasks
+}
+
+/// Finds the checkbox in a list item line, returning the byte offset of the
+/// character inside the brackets, whether it's ticked and the item's text.
+fn checkbox(line: &str) -> Option<(usize, bool, &str)> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    let marker = if rest.starts_with("- ") || rest.starts_with("* ") || rest.starts_with("+ ") {
+        1
+    } else if digits > 0 && (rest[digits..].starts_with(". ") || rest[digits..].starts_with(") ")) {
+        digits + 1
+    } else {
+        return None;
+    };
+
+    let after_marker = &rest[marker..];
+    let spaces = after_marker.len() - after_marker.trim_start().len();
+    let item = &after_marker[spaces..];
+    // `[-]` is org's partly done box, which is still open
+    let done = match item.get(..3)? {
+        "[ ]" | "[-]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    if !(item.len() == 3 || item[3..].starts_with(' ')) {
+        return None;
+    }
+
+    let offset = indent + marker + spaces + 1;
+    Some((offset, done, item[3..].trim()))
+}
+
+/// Ticks or unticks a task in its note. The line has to hold the same task
+/// it did when the note was indexed, so an edit made since isn't clobbered.
+pub fn toggle(path: &Path, task: &Task) -> io::Result<()> {
+    let contents = std::fs::read_to_string(path)?;
+
+    let mut line_start = 0;
+    for _ in 1..task.line {
+        line_start += contents[line_start..]
+            .find('\n')
+            .map(|i| i + 1)
+            .ok_or_else(changed)?;
+    }
+    let line_end = contents[line_start..]
+        .find('\n')
+        .map(|i| line_start + i)
+        .unwrap_or(contents.len());
+
+    let (offset, done, text) = checkbox(&contents[line_start..line_end]).ok_or_else(changed)?;
+    if done != task.done || text != task.text.as_ref() {
+        return Err(changed());
+    }
+
+    let offset = line_start + offset;
+    let mark = if task.done { " " } else { "x" };
+    let toggled = format!("{}{}{}", &contents[..offset], mark, &contents[offset + 1..]);
+    std::fs::write(path, toggled)
+}
+
+fn changed() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "the task changed on disk")
+}
@@ -0,0 +1,86 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::format::NoteFormat;
+use crate::frontmatter::FrontMatter;
+
+/// How a note's display title is worked out from its file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TitleStrategy {
+    /// The file name without its extension
+    FileName,
+    /// The `title:` key of the note's front matter
+    FrontMatter,
+    /// The note's first heading, `# heading` in markdown
+    Heading,
+    /// The first non-empty line of the note
+    FirstLine,
+}
+
+impl Default for TitleStrategy {
+    fn default() -> Self {
+        TitleStrategy::FileName
+    }
+}
+
+impl FromStr for TitleStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "filename" => Ok(TitleStrategy::FileName),
+            "frontmatter" => Ok(TitleStrategy::FrontMatter),
+            "heading" => Ok(TitleStrategy::Heading),
+            "firstline" => Ok(TitleStrategy::FirstLine),
+            _ => Err(format!(
+                "unknown title strategy '{}', expected one of: filename, frontmatter, heading, firstline",
+                s
+            )),
+        }
+    }
+}
+
+impl TitleStrategy {
+    /// Works out the title of a note from its front matter and body, falling
+    /// back to the file name when the note doesn't have what the strategy is
+    /// looking for.
+    pub fn title(
+        self,
+        path: &Path,
+        format: &dyn NoteFormat,
+        front_matter: &FrontMatter,
+        body: &str,
+    ) -> String {
+        let title = match self {
+            TitleStrategy::FileName => None,
+            TitleStrategy::FrontMatter => front_matter.get("title").map(str::to_string),
+            TitleStrategy::Heading => format.heading(body).map(str::to_string),
+            TitleStrategy::FirstLine => body
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(|line| line.to_string()),
+        };
+
+        match title {
+            Some(title) if !title.is_empty() => title,
+            _ => path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The starting contents of a new note in `format`, so that the new
+    /// note's title comes out as `title` under this strategy.
+    pub fn new_note_contents(self, format: &dyn NoteFormat, title: &str) -> String {
+        match self {
+            TitleStrategy::FileName => String::new(),
+            // Without metadata the title falls back to the file name, which
+            // is the title anyway
+            TitleStrategy::FrontMatter => format.title_metadata(title).unwrap_or_default(),
+            TitleStrategy::Heading => format.heading_line(title),
+            TitleStrategy::FirstLine => format!("{}\n\n", title),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::path::{Path, PathBuf};
+
+use crate::rename::{self, RenameError, RenamePlan};
+
+/// A change to the notes on disk that can be taken back within the session
+#[derive(Clone, Debug)]
+pub enum UndoAction {
+    Rename(RenamePlan),
+    /// Deleting, archiving and moving all move a note from one place to another
+    Move {
+        verb: &'static str,
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Duplicate {
+        original: PathBuf,
+        copy: PathBuf,
+        /// What the copy held when it was made, so an edited copy is kept
+        contents: Vec<u8>,
+    },
+}
+
+impl UndoAction {
+    pub fn undo(&self) -> Result<(), RenameError> {
+        match self {
+            UndoAction::Rename(plan) => plan.inverse().apply(),
+            UndoAction::Move { from, to, .. } => rename::move_back(to, from),
+            UndoAction::Duplicate { copy, contents, .. } => {
+                let current = std::fs::read(copy).map_err(RenameError::Io)?;
+                if &current != contents {
+                    return Err(RenameError::Changed(copy.clone()));
+                }
+                std::fs::remove_file(copy).map_err(RenameError::Io)
+            }
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            UndoAction::Rename(plan) => format!(
+                "rename of {} to {}",
+                file_name(&plan.from),
+                file_name(&plan.to)
+            ),
+            UndoAction::Move { verb, from, .. } => format!("{} of {}", verb, file_name(from)),
+            UndoAction::Duplicate { original, .. } => {
+                format!("duplicate of {}", file_name(original))
+            }
+        }
+    }
+
+    /// Where the note this action moved is after undoing it, and where it was
+    /// before, so the selection can follow it back.
+    pub fn restored(&self) -> Option<(&Path, &Path)> {
+        match self {
+            UndoAction::Rename(plan) => Some((&plan.to, &plan.from)),
+            UndoAction::Move { from, to, .. } => Some((to, from)),
+            UndoAction::Duplicate { .. } => None,
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,185 @@
+//! Turning file system changes under the notes root into index updates.
+//! Watchers live in the app, which feeds their events in as [`Change`]s over
+//! a channel, so anything that can produce changes can drive an update.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::search;
+
+/// How long the changes have to go quiet before they're applied. Editors
+/// save in bursts of writes, renames and removes that add up to one change.
+pub const QUIET_PERIOD: Duration = Duration::from_millis(200);
+
+/// The longest changes are held back when they never go quiet, such as
+/// while a sync client is writing a stream of files
+pub const MAX_WAIT: Duration = Duration::from_secs(2);
+
+/// The paths one event from a file watcher was about. What kind of event it
+/// was isn't kept, since [`Batch::from_changes`] goes by the disk instead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub paths: Vec<PathBuf>,
+}
+
+/// The net effect of a burst of changes: the files and folders that are now
+/// there to be re-read, and the ones that are gone. Paths are joined onto the
+/// root as given, so they line up with the paths of notes from the walk.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Batch {
+    pub changed: BTreeSet<PathBuf>,
+    pub removed: BTreeSet<PathBuf>,
+}
+
+impl Batch {
+    /// Coalesces `changes` under `root`, leaving out the hidden paths the
+    /// walk skips and anything outside the root. Events arrive out of order
+    /// and get merged by the OS, so whether a path counts as changed or
+    /// removed is settled by looking at the disk rather than the event kind.
+    pub fn from_changes(root: &str, changes: &[Change]) -> Batch {
+        let root = Path::new(root);
+        let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+
+        let mut paths = BTreeSet::new();
+        for change in changes {
+            for path in &change.paths {
+                let relative = match path
+                    .strip_prefix(&canonical_root)
+                    .or_else(|_| path.strip_prefix(root))
+                {
+                    Ok(relative) if relative.as_os_str().is_empty() => continue,
+                    Ok(relative) => relative,
+                    Err(_) => continue,
+                };
+                if !search::is_hidden_path(relative) {
+                    paths.insert(root.join(relative));
+                }
+            }
+        }
+
+        let mut batch = Batch::default();
+        for path in paths {
+            if path.exists() {
+                batch.changed.insert(path);
+            } else {
+                batch.removed.insert(path);
+            }
+        }
+        batch
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    /// Whether the note at `path` was changed or removed by this batch,
+    /// directly or through one of its folders
+    pub fn affects(&self, path: &Path) -> bool {
+        self.changed
+            .iter()
+            .chain(self.removed.iter())
+            .any(|changed| path.starts_with(changed))
+    }
+}
+
+/// Waits for changes to `root` and hands them to `apply` a batch at a time,
+/// once they've gone quiet for `quiet` or `max_wait` has passed since the
+/// first of them. Returns when every sender is gone.
+pub fn debounce<F>(
+    changes: &Receiver<Change>,
+    root: &str,
+    quiet: Duration,
+    max_wait: Duration,
+    mut apply: F,
+) where
+    F: FnMut(Batch),
+{
+    while let Ok(first) = changes.recv() {
+        let deadline = Instant::now() + max_wait;
+        let mut pending = vec![first];
+        let disconnected = loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left == Duration::from_secs(0) {
+                break false;
+            }
+            match changes.recv_timeout(quiet.min(left)) {
+                Ok(change) => pending.push(change),
+                Err(RecvTimeoutError::Timeout) => break false,
+                Err(RecvTimeoutError::Disconnected) => break true,
+            }
+        };
+
+        let batch = Batch::from_changes(root, &pending);
+        if !batch.is_empty() {
+            apply(batch);
+        }
+        if disconnected {
+            return;
+        }
+    }
+}
+
+/// The modified time and size of every file the walk finds, for polling
+/// where the OS won't send file events
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    files: HashMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl Snapshot {
+    pub fn scan(root: &str) -> Snapshot {
+        let files = search::walk(root)
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((
+                    entry.into_path(),
+                    (metadata.modified().ok(), metadata.len()),
+                ))
+            })
+            .collect();
+        Snapshot { files }
+    }
+
+    /// What's been created, modified and removed since `earlier`
+    pub fn changes_since(&self, earlier: &Snapshot) -> Vec<Change> {
+        let changed = self
+            .files
+            .iter()
+            .filter(|(path, stamp)| earlier.files.get(*path) != Some(stamp))
+            .map(|(path, _)| path);
+        let removed = earlier
+            .files
+            .keys()
+            .filter(|path| !self.files.contains_key(*path));
+        changed
+            .chain(removed)
+            .map(|path| Change {
+                paths: vec![path.clone()],
+            })
+            .collect()
+    }
+}
+
+/// Scans `root` every `interval`, sending what changed between scans.
+/// Returns when the root goes missing or nothing is listening any more.
+pub fn poll(root: &str, interval: Duration, changes: &Sender<Change>) {
+    let mut last = Snapshot::scan(root);
+    loop {
+        thread::sleep(interval);
+        if !Path::new(root).is_dir() {
+            return;
+        }
+        let next = Snapshot::scan(root);
+        for change in next.changes_since(&last) {
+            if changes.send(change).is_err() {
+                return;
+            }
+        }
+        last = next;
+    }
+}
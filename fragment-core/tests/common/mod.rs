@@ -0,0 +1,109 @@
+//! A temporary notes folder to run the engine against
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicU64;
+use std::time::{Duration, SystemTime};
+
+use filetime::FileTime;
+use tempfile::{Builder, TempDir};
+
+use fragment_core::search::{self, SearchResults, SortMethod};
+use fragment_core::title::TitleStrategy;
+use fragment_core::Note;
+
+/// A notes root in a temp dir, removed when dropped
+pub struct Notes {
+    dir: TempDir,
+}
+
+impl Notes {
+    pub fn new() -> Self {
+        Self::with_prefix("fragment-notes")
+    }
+
+    /// A root that is itself hidden, like `~/.notes`
+    pub fn hidden() -> Self {
+        Self::with_prefix(".fragment-notes")
+    }
+
+    fn with_prefix(prefix: &str) -> Self {
+        Notes {
+            dir: Builder::new()
+                .prefix(prefix)
+                .tempdir()
+                .expect("Couldn't create temp dir"),
+        }
+    }
+
+    pub fn root(&self) -> &str {
+        self.dir.path().to_str().expect("Temp dir isn't unicode")
+    }
+
+    pub fn path(&self, relative: &str) -> PathBuf {
+        self.dir.path().join(relative)
+    }
+
+    /// Writes a file, creating any folders on the way
+    pub fn write(&self, relative: &str, contents: impl AsRef<[u8]>) -> &Self {
+        let path = self.path(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("Couldn't create folders");
+        }
+        fs::write(&path, contents).expect("Couldn't write file");
+        self
+    }
+
+    /// Writes a file modified `age` seconds ago, so tests can control the
+    /// date order without sleeping
+    pub fn write_aged(&self, relative: &str, contents: impl AsRef<[u8]>, age: u64) -> &Self {
+        self.write(relative, contents);
+        let modified = SystemTime::now() - Duration::from_secs(age);
+        filetime::set_file_mtime(self.path(relative), FileTime::from_system_time(modified))
+            .expect("Couldn't set mtime");
+        self
+    }
+
+    pub fn list(&self, titles: TitleStrategy) -> Vec<Note> {
+        search::list_of_all_files(
+            self.root(),
+            SortMethod::DateNewest,
+            titles,
+            &AtomicU64::new(0),
+            1,
+        )
+    }
+
+    pub fn search(&self, query: &str) -> SearchResults {
+        search::search(
+            query,
+            self.root(),
+            TitleStrategy::FileName,
+            &AtomicU64::new(0),
+            1,
+        )
+        .expect("Search failed")
+    }
+}
+
+/// The notes' paths relative to `root`, in order, with `/` separators
+pub fn relative_paths(root: &str, notes: &[Note]) -> Vec<String> {
+    notes
+        .iter()
+        .map(|note| {
+            Path::new(note.path.as_ref())
+                .strip_prefix(root)
+                .expect("Note outside the root")
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .collect()
+}
+
+pub fn titles(notes: &[Note]) -> Vec<&str> {
+    notes.iter().map(|note| note.title.as_ref()).collect()
+}
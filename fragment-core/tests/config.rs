@@ -0,0 +1,56 @@
+mod common;
+
+use std::time::Duration;
+
+use fragment_core::config::{Config, WatchMode, MIN_POLL_INTERVAL};
+
+use common::Notes;
+
+fn load(config: &str) -> Config {
+    let notes = Notes::new();
+    notes.write(".fragment/config.toml", config);
+    Config::load(notes.root())
+}
+
+#[test]
+fn a_missing_file_means_the_defaults() {
+    let notes = Notes::new();
+    assert_eq!(Config::load(notes.root()), Config::default());
+}
+
+#[test]
+fn values_are_read_with_or_without_quotes() {
+    let config = load(
+        "# Polling for the network drive\n\
+         watcher = poll\n\
+         poll_interval = \"0.5\"\n\
+         note_extension = '.org'\n\
+         editor = \"nvim +{line} {path}\"\n",
+    );
+    assert_eq!(config.watcher, WatchMode::Poll);
+    assert_eq!(config.poll_interval, Duration::from_millis(500));
+    assert_eq!(config.note_extension, "org");
+    assert_eq!(config.editor.as_deref(), Some("nvim +{line} {path}"));
+    assert_eq!(config.archive_folder, "archive");
+}
+
+#[test]
+fn tiny_poll_intervals_are_raised_to_the_minimum() {
+    for interval in &["0.0001", "0.05", "1e-9"] {
+        let config = load(&format!("poll_interval = {}", interval));
+        assert_eq!(config.poll_interval, MIN_POLL_INTERVAL, "{}", interval);
+    }
+}
+
+#[test]
+fn bad_poll_intervals_keep_the_default() {
+    for interval in &["0", "-1", "soon", "NaN"] {
+        let config = load(&format!("poll_interval = {}", interval));
+        assert_eq!(
+            config.poll_interval,
+            Config::default().poll_interval,
+            "{}",
+            interval
+        );
+    }
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use std::fs;
+
+use fragment_core::note;
+use fragment_core::title::TitleStrategy;
+
+use common::{titles, Notes};
+
+#[test]
+fn new_notes_get_the_configured_extension() {
+    let notes = Notes::new();
+    let root = notes.path("");
+
+    let path = note::create(&root, "Groceries", "md", TitleStrategy::FileName).unwrap();
+    assert_eq!(path, notes.path("Groceries.md"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+    let path = note::create(&root, "Agenda", "org", TitleStrategy::FileName).unwrap();
+    assert_eq!(path, notes.path("Agenda.org"));
+}
+
+#[test]
+fn dots_in_the_title_are_kept() {
+    let notes = Notes::new();
+    let path = note::create(&notes.path(""), "v1.2 notes", "md", TitleStrategy::FileName).unwrap();
+    assert_eq!(path, notes.path("v1.2 notes.md"));
+    assert!(path.exists());
+}
+
+#[test]
+fn blank_titles_are_refused() {
+    let notes = Notes::new();
+    for title in &["", "  \t"] {
+        let err = note::create(&notes.path(""), title, "md", TitleStrategy::FileName).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+    assert!(fs::read_dir(notes.root()).unwrap().next().is_none());
+    assert!(!std::path::Path::new(&format!("{}.md", notes.root())).exists());
+}
+
+#[test]
+fn new_notes_come_back_with_the_title_they_were_made_with() {
+    let strategies = [
+        TitleStrategy::FileName,
+        TitleStrategy::FrontMatter,
+        TitleStrategy::Heading,
+        TitleStrategy::FirstLine,
+    ];
+    for &strategy in &strategies {
+        for extension in &["md", "org", "txt"] {
+            let notes = Notes::new();
+            note::create(&notes.path(""), "Reading list", extension, strategy).unwrap();
+
+            let list = notes.list(strategy);
+            assert_eq!(
+                titles(&list),
+                vec!["Reading list"],
+                "{:?} with .{}",
+                strategy,
+                extension
+            );
+        }
+    }
+}
+
+#[test]
+fn heading_notes_start_with_the_format_s_heading() {
+    let notes = Notes::new();
+    let root = notes.path("");
+
+    let markdown = note::create(&root, "Ideas", "md", TitleStrategy::Heading).unwrap();
+    assert!(fs::read_to_string(markdown).unwrap().starts_with("# Ideas"));
+
+    let org = note::create(&root, "Ideas", "org", TitleStrategy::Heading).unwrap();
+    assert!(fs::read_to_string(org).unwrap().starts_with("* Ideas"));
+}
+
+#[test]
+fn existing_notes_are_left_alone() {
+    let notes = Notes::new();
+    notes.write("Journal.md", "already written");
+
+    let path = note::create(&notes.path(""), "Journal", "md", TitleStrategy::Heading).unwrap();
+    assert_eq!(fs::read_to_string(path).unwrap(), "already written");
+}
+
+#[test]
+fn creating_in_a_missing_folder_fails() {
+    let notes = Notes::new();
+    let missing = notes.path("nowhere");
+
+    assert!(note::create(&missing, "Note", "md", TitleStrategy::FileName).is_err());
+}
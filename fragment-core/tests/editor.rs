@@ -0,0 +1,40 @@
+use std::io;
+use std::path::Path;
+
+use fragment_core::editor;
+
+#[test]
+fn arguments_split_on_whitespace_outside_quotes() {
+    assert_eq!(
+        editor::split_args("nvim  +{line}\t{path}"),
+        vec!["nvim", "+{line}", "{path}"]
+    );
+    assert_eq!(
+        editor::split_args(r#""/Applications/My Editor.app/bin/edit" -g '{path}:{line}'"#),
+        vec![
+            "/Applications/My Editor.app/bin/edit",
+            "-g",
+            "{path}:{line}"
+        ]
+    );
+    // A quote of the other kind is just a character inside quotes
+    assert_eq!(
+        editor::split_args(r#"edit "it's here""#),
+        vec!["edit", "it's here"]
+    );
+    assert!(editor::split_args("   ").is_empty());
+}
+
+#[test]
+fn a_missing_editor_is_an_error_not_a_panic() {
+    let result = editor::open(
+        Some("fragment-no-such-editor-here {path}"),
+        Path::new("note.md"),
+        1,
+        1,
+    );
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+
+    let result = editor::open(Some("''"), Path::new("note.md"), 1, 1);
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,51 @@
+use fragment_core::frontmatter;
+
+#[test]
+fn yaml_scalars_and_lists() {
+    let (front_matter, body) = frontmatter::parse(
+        "---\nTitle: \"Quoted\"\ntags: [work, 'home']\naliases:\n  - one\n  - two\nempty:\n---\n\nBody\n",
+    );
+    assert_eq!(front_matter.get("title"), Some("Quoted"));
+    assert_eq!(front_matter.get_all("tags"), ["work", "home"]);
+    assert_eq!(front_matter.get_all("aliases"), ["one", "two"]);
+    assert!(front_matter.get_all("empty").is_empty());
+    assert!(front_matter.get_all("missing").is_empty());
+    assert_eq!(body, "Body\n");
+}
+
+#[test]
+fn toml_blocks() {
+    let (front_matter, body) =
+        frontmatter::parse("+++\ntitle = \"Plans\"\ntags = [\"a\", \"b\"]\n[extra]\n+++\nBody");
+    assert_eq!(front_matter.get("title"), Some("Plans"));
+    assert_eq!(front_matter.get_all("tags"), ["a", "b"]);
+    assert_eq!(body, "Body");
+}
+
+#[test]
+fn crlf_and_byte_order_marks() {
+    let (front_matter, body) =
+        frontmatter::parse("\u{feff}---\r\ntitle: Windows\r\n---\r\nBody\r\n");
+    assert_eq!(front_matter.get("title"), Some("Windows"));
+    assert_eq!(body, "Body\r\n");
+}
+
+#[test]
+fn notes_without_a_closed_block_are_all_body() {
+    for contents in &[
+        "No front matter",
+        "---\ntitle: never closed\n",
+        "--- not a delimiter\n",
+    ] {
+        let (front_matter, body) = frontmatter::parse(contents);
+        assert!(front_matter.is_empty(), "{:?}", contents);
+        assert_eq!(&body, contents);
+    }
+}
+
+#[test]
+fn a_closing_delimiter_at_the_very_end() {
+    let (front_matter, body) = frontmatter::parse("---\ntitle: Last\n---");
+    assert_eq!(front_matter.get("title"), Some("Last"));
+    assert_eq!(body, "");
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use std::sync::atomic::AtomicU64;
+
+use fragment_core::search;
+
+use common::{relative_paths, Notes};
+
+#[test]
+fn stops_at_the_first_match_in_each_note() {
+    let notes = Notes::new();
+    notes.write("note.md", "first line\nthe needle\nanother needle\n");
+
+    let results = notes.search("needle");
+    assert_eq!(results.matches.len(), 1);
+    let found = &results.matches[0];
+    assert_eq!(found.found_at, Some((2, 5)));
+    assert_eq!(found.found_line.as_deref(), Some("2: the needle"));
+}
+
+#[test]
+fn columns_count_characters_not_bytes() {
+    let notes = Notes::new();
+    notes.write("note.md", "日本語 needle");
+
+    let results = notes.search("needle");
+    assert_eq!(results.matches[0].found_at, Some((1, 5)));
+}
+
+#[test]
+fn titles_match_when_the_contents_dont() {
+    let notes = Notes::new();
+    notes
+        .write_aged("needle in a haystack.md", "nothing to see", 20)
+        .write_aged("haystack.md", "just hay", 10);
+
+    let results = notes.search("needle");
+    assert_eq!(
+        relative_paths(notes.root(), &results.matches),
+        vec!["needle in a haystack.md"]
+    );
+    assert_eq!(results.matches[0].found_at, None);
+}
+
+#[test]
+fn matches_keep_the_date_order() {
+    let notes = Notes::new();
+    notes
+        .write_aged("a.md", "needle", 30)
+        .write_aged("b.md", "needle", 10)
+        .write_aged("c.md", "hay", 5)
+        .write_aged("d.md", "needle", 20);
+
+    let results = notes.search("needle");
+    assert_eq!(
+        relative_paths(notes.root(), &results.matches),
+        vec!["b.md", "d.md", "a.md"]
+    );
+    assert_eq!(results.notes.len(), 4);
+}
+
+#[test]
+fn filters_pick_the_notes_before_grepping() {
+    let notes = Notes::new();
+    notes
+        .write("tagged.md", "---\ntags: [work]\n---\nneedle")
+        .write("untagged.md", "work: needle");
+
+    // Both notes hold "needle", so only the tag filter tells them apart
+    let results = notes.search("tag:work needle");
+    assert_eq!(
+        relative_paths(notes.root(), &results.matches),
+        vec!["tagged.md"]
+    );
+}
+
+#[test]
+fn an_empty_pattern_matches_everything() {
+    let notes = Notes::new();
+    notes.write("a.md", "").write("b.md", "text");
+
+    assert_eq!(notes.search("").matches.len(), 2);
+}
+
+#[test]
+fn a_bad_regex_is_an_error() {
+    let notes = Notes::new();
+    notes.write("a.md", "");
+
+    let list = notes.list(Default::default());
+    assert!(search::grep_life("(unclosed", &list, &AtomicU64::new(0), 1).is_err());
+}
+
+#[test]
+fn a_newer_search_cancels_the_grep() {
+    let notes = Notes::new();
+    for i in 0..10 {
+        notes.write(&format!("note{}.md", i), "needle");
+    }
+    let list = notes.list(Default::default());
+
+    let matches = search::grep_life("needle", &list, &AtomicU64::new(2), 1).unwrap();
+    assert!(matches.is_empty());
+}
+
+#[test]
+fn search_cancels_itself_when_already_superseded() {
+    let notes = Notes::new();
+    notes.write("note.md", "needle");
+
+    // Each search bumps the counter, so starting at its own number means a
+    // newer one has already begun
+    let sequence = AtomicU64::new(1);
+    let results = search::search("needle", notes.root(), Default::default(), &sequence, 1)
+        .expect("Search failed");
+    assert!(results.matches.is_empty());
+    assert_eq!(results.notes.len(), 0);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use fragment_core::history::{self, History};
+
+use common::Notes;
+
+const DAY: u64 = 86_400;
+
+fn day(n: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(n * DAY)
+}
+
+fn queries<'a>(entries: impl IntoIterator<Item = &'a history::Entry>) -> Vec<&'a str> {
+    entries
+        .into_iter()
+        .map(|entry| entry.query.as_str())
+        .collect()
+}
+
+#[test]
+fn recording_again_moves_a_query_to_the_front() {
+    let mut history = History::default();
+    history.record("tag:inbox", day(1));
+    history.record("meeting", day(2));
+    history.record("  tag:inbox ", day(3));
+    history.record("   ", day(4));
+
+    assert_eq!(queries(history.recent()), vec!["tag:inbox", "meeting"]);
+    assert_eq!(history.recent()[0].uses, 2);
+    assert_eq!(history.recent()[0].last_used, 3 * DAY);
+}
+
+#[test]
+fn only_the_most_recent_queries_are_kept() {
+    let mut history = History::default();
+    for n in 0..history::LIMIT + 10 {
+        history.record(&format!("query {}", n), day(n as u64));
+    }
+
+    assert_eq!(history.recent().len(), history::LIMIT);
+    assert_eq!(
+        history.recent().last().unwrap().query,
+        format!("query {}", 10)
+    );
+}
+
+#[test]
+fn ranking_weighs_uses_by_recency() {
+    let mut history = History::default();
+    for _ in 0..5 {
+        history.record("often, long ago", day(0));
+    }
+    history.record("once, today", day(100));
+    history.record("twice, this week", day(97));
+    history.record("twice, this week", day(97));
+
+    assert_eq!(
+        queries(history.ranked(day(100))),
+        vec!["twice, this week", "once, today", "often, long ago"]
+    );
+}
+
+#[test]
+fn history_round_trips_and_clears() {
+    let notes = Notes::new();
+    assert!(History::load(notes.root()).is_empty());
+
+    let mut history = History::default();
+    history.record("links:\"Plan\"", day(1));
+    history.record("tag:inbox", day(2));
+    history.record("tag:inbox", day(3));
+    history.save(notes.root()).unwrap();
+    assert_eq!(History::load(notes.root()), history);
+
+    history.clear();
+    history.save(notes.root()).unwrap();
+    assert!(History::load(notes.root()).is_empty());
+}
+
+#[test]
+fn hand_edited_files_are_read_most_recent_first() {
+    let notes = Notes::new();
+    notes.write(
+        ".fragment/history.toml",
+        "# searches\n\"old\" = [4, 100]\nbroken\n\"new\" = [1, 200]\n\"no uses\" = []\n",
+    );
+
+    assert_eq!(
+        queries(History::load(notes.root()).recent()),
+        vec!["new", "old"]
+    );
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use filetime::FileTime;
+use fragment_core::index::Index;
+use fragment_core::title::TitleStrategy;
+use fragment_core::watch::{Batch, Change};
+
+use common::Notes;
+
+/// Rewrites `relative` without moving its modified time, like an edit made
+/// within the same tick of the clock
+fn rewrite_in_place(notes: &Notes, relative: &str, contents: &str) {
+    let path = notes.path(relative);
+    let modified = FileTime::from_last_modification_time(&fs::metadata(&path).unwrap());
+    fs::write(&path, contents).unwrap();
+    filetime::set_file_mtime(&path, modified).unwrap();
+}
+
+fn tags(index: &mut Index, notes: &Notes, relative: &str) -> Vec<String> {
+    let note = index
+        .read_note(notes.root(), &notes.path(relative), TitleStrategy::FileName)
+        .unwrap();
+    note.tags.iter().map(|tag| tag.to_string()).collect()
+}
+
+#[test]
+fn unchanged_notes_come_from_the_index_until_a_batch_touches_them() {
+    let notes = Notes::new();
+    notes.write_aged("note.md", "#before", 60);
+    let mut index = Index::new();
+    assert_eq!(tags(&mut index, &notes, "note.md"), vec!["before"]);
+    assert_eq!(index.len(), 1);
+
+    rewrite_in_place(&notes, "note.md", "#during");
+    assert_eq!(tags(&mut index, &notes, "note.md"), vec!["before"]);
+
+    let changes = vec![Change {
+        paths: vec![notes.path("note.md")],
+    }];
+    index.forget(&Batch::from_changes(notes.root(), &changes));
+    assert_eq!(tags(&mut index, &notes, "note.md"), vec!["during"]);
+}
+
+#[test]
+fn notes_changed_just_before_reading_are_read_again() {
+    let notes = Notes::new();
+    notes.write("note.md", "#before");
+    let mut index = Index::new();
+    assert_eq!(tags(&mut index, &notes, "note.md"), vec!["before"]);
+    assert!(index.is_empty());
+
+    rewrite_in_place(&notes, "note.md", "#during");
+    assert_eq!(tags(&mut index, &notes, "note.md"), vec!["during"]);
+}
+
+#[test]
+fn removed_notes_are_forgotten() {
+    let notes = Notes::new();
+    notes
+        .write_aged("kept.md", "", 60)
+        .write_aged("folder/a.md", "", 60)
+        .write_aged("folder/deep/b.md", "", 60);
+    let mut index = Index::new();
+    for relative in &["kept.md", "folder/a.md", "folder/deep/b.md"] {
+        index.read_note(notes.root(), &notes.path(relative), TitleStrategy::FileName);
+    }
+    assert_eq!(index.len(), 3);
+
+    fs::remove_dir_all(notes.path("folder")).unwrap();
+    let changes = vec![Change {
+        paths: vec![notes.path("folder")],
+    }];
+    index.forget(&Batch::from_changes(notes.root(), &changes));
+    assert_eq!(index.len(), 1);
+    assert!(index
+        .read_note(
+            notes.root(),
+            Path::new(&notes.path("kept.md")),
+            TitleStrategy::FileName
+        )
+        .is_some());
+}
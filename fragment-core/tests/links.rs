@@ -0,0 +1,53 @@
+mod common;
+
+use fragment_core::links;
+use fragment_core::title::TitleStrategy;
+
+use common::{titles, Notes};
+
+#[test]
+fn targets_drop_labels_and_headings() {
+    let found = links::extract(
+        "See [[Plans]], [[Plans|the plans]] and [[ Budget #Q3 ]].\n\
+         Not [[]] or [[split\nacross lines]], nor an [[unclosed one",
+    );
+    let found: Vec<&str> = found.iter().map(|link| link.as_ref()).collect();
+    assert_eq!(found, vec!["Plans", "Budget"]);
+}
+
+#[test]
+fn links_resolve_by_title_file_name_or_alias() {
+    let notes = Notes::new();
+    notes
+        .write(
+            "2020-05-01.md",
+            "---\ntitle: Kickoff\naliases: [Start]\n---\n",
+        )
+        .write("other.md", "");
+    let list = notes.list(TitleStrategy::FrontMatter);
+    let kickoff = list
+        .iter()
+        .find(|note| note.title.as_ref() == "Kickoff")
+        .unwrap();
+
+    for target in &["kickoff", "2020-05-01", "START"] {
+        assert!(links::resolves_to(target, kickoff), "{}", target);
+    }
+    assert!(!links::resolves_to("other", kickoff));
+}
+
+#[test]
+fn backlinks_leave_out_the_note_itself() {
+    let notes = Notes::new();
+    notes
+        .write_aged("target.md", "[[target]]", 30)
+        .write_aged("linker.md", "[[Target|label]]", 20)
+        .write_aged("unrelated.md", "[[elsewhere]]", 10);
+    let list = notes.list(TitleStrategy::FileName);
+    let target = list
+        .iter()
+        .find(|note| note.title.as_ref() == "target")
+        .unwrap();
+
+    assert_eq!(titles(&links::backlinks(target, &list)), vec!["linker"]);
+}
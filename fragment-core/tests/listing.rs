@@ -0,0 +1,224 @@
+mod common;
+
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+
+use fragment_core::search::{self, SortMethod};
+use fragment_core::title::TitleStrategy;
+
+use common::{relative_paths, titles, Notes};
+
+#[test]
+fn lists_every_file_newest_first() {
+    let notes = Notes::new();
+    notes
+        .write_aged("old.md", "old", 300)
+        .write_aged("newest.md", "newest", 10)
+        .write_aged("middle.md", "middle", 100);
+
+    let list = notes.list(TitleStrategy::FileName);
+    assert_eq!(
+        relative_paths(notes.root(), &list),
+        vec!["newest.md", "middle.md", "old.md"]
+    );
+}
+
+#[test]
+fn unicode_names_keep_their_titles() {
+    let notes = Notes::new();
+    notes
+        .write_aged("café ☕.md", "", 20)
+        .write_aged("日本語/メモ.md", "", 10);
+
+    let list = notes.list(TitleStrategy::FileName);
+    assert_eq!(titles(&list), vec!["メモ", "café ☕"]);
+    assert_eq!(list[0].folder.as_ref(), "日本語");
+    assert_eq!(list[1].file_name.as_ref(), "café ☕.md");
+}
+
+#[test]
+fn hidden_folders_and_files_are_skipped() {
+    let notes = Notes::new();
+    notes
+        .write("visible.md", "")
+        .write(".git/config", "")
+        .write(".trash/deleted.md", "")
+        .write(".fragment/searches", "")
+        .write("folder/.hidden.md", "")
+        .write("folder/shown.md", "");
+
+    let mut paths = relative_paths(notes.root(), &notes.list(TitleStrategy::FileName));
+    paths.sort();
+    assert_eq!(paths, vec!["folder/shown.md", "visible.md"]);
+}
+
+#[test]
+fn a_hidden_root_is_still_walked() {
+    let notes = Notes::hidden();
+    notes.write("note.md", "").write(".git/config", "");
+
+    let list = notes.list(TitleStrategy::FileName);
+    assert_eq!(relative_paths(notes.root(), &list), vec!["note.md"]);
+}
+
+#[test]
+fn deep_nesting_records_the_folder() {
+    let notes = Notes::new();
+    let deep: Vec<String> = (0..30).map(|depth| format!("level{}", depth)).collect();
+    let relative = format!("{}/deep.md", deep.join("/"));
+    notes.write(&relative, "# Deep");
+
+    let list = notes.list(TitleStrategy::Heading);
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].title.as_ref(), "Deep");
+    let folder = deep.iter().collect::<std::path::PathBuf>();
+    assert_eq!(Path::new(list[0].folder.as_ref()), folder);
+}
+
+#[test]
+fn binary_files_are_listed_but_never_match() {
+    let notes = Notes::new();
+    let mut binary = b"needle\x00\x01\x02".to_vec();
+    binary.extend_from_slice(&[0xff; 1024]);
+    binary.extend(b"\nneedle\n");
+    notes
+        .write("image.png", &binary)
+        .write("text.md", "a needle here");
+
+    let results = notes.search("needle");
+    assert_eq!(results.notes.len(), 2);
+    assert_eq!(
+        relative_paths(notes.root(), &results.matches),
+        vec!["text.md"]
+    );
+}
+
+#[test]
+fn binary_files_arent_indexed() {
+    let notes = Notes::new();
+    notes
+        .write("data.bin", b"#tag [[link]] - [ ] task\x00\x01")
+        .write("photo.jpg", "#tag [[link]] - [ ] task");
+
+    let list = notes.list(TitleStrategy::FirstLine);
+    assert_eq!(list.len(), 2);
+    for note in &list {
+        assert!(note.tags.is_empty());
+        assert!(note.links.is_empty());
+        assert!(note.tasks.is_empty());
+        assert_eq!(note.word_count, 0);
+    }
+    let mut titles = titles(&list);
+    titles.sort();
+    assert_eq!(titles, vec!["data", "photo"]);
+}
+
+#[test]
+fn changed_notes_are_read_again() {
+    let notes = Notes::new();
+    notes.write_aged("note.md", "#before", 20);
+    assert_eq!(
+        notes.list(TitleStrategy::FileName)[0].tags[0].as_ref(),
+        "before"
+    );
+
+    notes.write_aged("note.md", "#after", 10);
+    assert_eq!(
+        notes.list(TitleStrategy::FileName)[0].tags[0].as_ref(),
+        "after"
+    );
+    assert_eq!(
+        notes.list(TitleStrategy::FirstLine)[0].title.as_ref(),
+        "#after"
+    );
+}
+
+#[test]
+fn large_files_are_read_whole() {
+    let notes = Notes::new();
+    let mut contents = "lorem ipsum dolor sit amet\n".repeat(200_000);
+    contents.push_str("- [ ] the last task\n");
+    notes.write("large.md", &contents);
+
+    let list = notes.list(TitleStrategy::FileName);
+    assert_eq!(list[0].word_count, 200_000 * 5 + 6);
+    assert_eq!(list[0].size, contents.len() as u64);
+    assert_eq!(list[0].tasks.len(), 1);
+    assert_eq!(list[0].tasks[0].line, 200_001);
+}
+
+#[test]
+fn front_matter_fills_in_the_index() {
+    let notes = Notes::new();
+    notes.write(
+        "meeting.md",
+        "---\ntitle: Weekly sync\ntags: [work, meetings]\naliases: [sync]\n\
+         created: 2020-05-01\n---\nSee [[agenda]].\n",
+    );
+
+    let list = notes.list(TitleStrategy::FrontMatter);
+    let note = &list[0];
+    assert_eq!(note.title.as_ref(), "Weekly sync");
+    assert!(note.tags.iter().any(|tag| tag.as_ref() == "work"));
+    assert!(note.tags.iter().any(|tag| tag.as_ref() == "meetings"));
+    assert_eq!(note.aliases.len(), 1);
+    assert_eq!(note.links.len(), 1);
+    assert!(note.created.is_some());
+}
+
+#[test]
+fn a_newer_search_cancels_the_listing() {
+    let notes = Notes::new();
+    for i in 0..10 {
+        notes.write(&format!("note{}.md", i), "");
+    }
+
+    let sequence = AtomicU64::new(2);
+    let list = search::list_of_all_files(
+        notes.root(),
+        SortMethod::DateNewest,
+        TitleStrategy::FileName,
+        &sequence,
+        1,
+    );
+    assert!(list.is_empty());
+}
+
+#[test]
+fn the_current_search_runs_to_the_end() {
+    let notes = Notes::new();
+    for i in 0..10 {
+        notes.write(&format!("note{}.md", i), "");
+    }
+
+    let sequence = AtomicU64::new(3);
+    let list = search::list_of_all_files(
+        notes.root(),
+        SortMethod::DateNewest,
+        TitleStrategy::FileName,
+        &sequence,
+        3,
+    );
+    assert_eq!(list.len(), 10);
+}
+
+#[test]
+fn notes_sort_by_date_or_title() {
+    let notes = Notes::new();
+    notes
+        .write_aged("b.md", "", 30)
+        .write_aged("A.md", "", 20)
+        .write_aged("c.md", "", 10);
+    let mut list = notes.list(TitleStrategy::FileName);
+
+    search::sort(&mut list, SortMethod::DateOldest);
+    assert_eq!(titles(&list), vec!["b", "A", "c"]);
+    search::sort(&mut list, SortMethod::TitleAZ);
+    assert_eq!(titles(&list), vec!["A", "b", "c"]);
+    search::sort(&mut list, SortMethod::TitleZA);
+    assert_eq!(titles(&list), vec!["c", "b", "A"]);
+    search::sort(&mut list, SortMethod::DateNewest);
+    assert_eq!(titles(&list), vec!["c", "A", "b"]);
+
+    assert_eq!("title-reversed".parse(), Ok(SortMethod::TitleZA));
+}
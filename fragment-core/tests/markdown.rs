@@ -0,0 +1,168 @@
+use fragment_core::markdown::{parse, parse_inline, Block, Link, Span, Style};
+
+fn plain(text: &str) -> Span {
+    Span {
+        text: text.to_string(),
+        style: Style::default(),
+    }
+}
+
+fn styled(text: &str, style: Style) -> Span {
+    Span {
+        text: text.to_string(),
+        style,
+    }
+}
+
+#[test]
+fn headings_take_their_level() {
+    assert_eq!(
+        parse("# Title\n### Section ###\n#hashtag\n####### Seven"),
+        vec![
+            Block::Heading {
+                level: 1,
+                spans: vec![plain("Title")],
+            },
+            Block::Heading {
+                level: 3,
+                spans: vec![plain("Section")],
+            },
+            Block::Paragraph(vec![plain("#hashtag ####### Seven")]),
+        ]
+    );
+}
+
+#[test]
+fn emphasis_nests() {
+    let bold = Style {
+        bold: true,
+        ..Style::default()
+    };
+    let bold_italic = Style {
+        italic: true,
+        ..bold.clone()
+    };
+    assert_eq!(
+        parse_inline("a **bold _and italic_** ~~gone~~"),
+        vec![
+            plain("a "),
+            styled("bold ", bold),
+            styled("and italic", bold_italic),
+            plain(" "),
+            styled(
+                "gone",
+                Style {
+                    strikethrough: true,
+                    ..Style::default()
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn underscores_and_asterisks_in_words_and_sums_stay_plain() {
+    for text in &["snake_case_names", "2 * 3 * 4", "a * b", "file_name_v2.md"] {
+        assert_eq!(parse_inline(text), vec![plain(text)], "{}", text);
+    }
+}
+
+#[test]
+fn code_fences_keep_their_contents_verbatim() {
+    assert_eq!(
+        parse("```rust\nlet x = *y;\n\n# not a heading\n```\nafter"),
+        vec![
+            Block::CodeBlock {
+                language: Some("rust".to_string()),
+                code: "let x = *y;\n\n# not a heading".to_string(),
+            },
+            Block::Paragraph(vec![plain("after")]),
+        ]
+    );
+}
+
+#[test]
+fn an_unclosed_fence_runs_to_the_end() {
+    assert_eq!(
+        parse("before\n~~~\ncode\n**still code**"),
+        vec![
+            Block::Paragraph(vec![plain("before")]),
+            Block::CodeBlock {
+                language: None,
+                code: "code\n**still code**".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn task_items_are_checked_or_not() {
+    let item = |depth, marker: &str, checked, text| Block::ListItem {
+        depth,
+        marker: marker.to_string(),
+        checked,
+        spans: vec![plain(text)],
+    };
+    assert_eq!(
+        parse("- [ ] open\n  - [x] done\n* [X] also done\n1. [] not a task\n2) plain"),
+        vec![
+            item(0, "-", Some(false), "open"),
+            item(1, "-", Some(true), "done"),
+            item(0, "-", Some(true), "also done"),
+            item(0, "1.", None, "[] not a task"),
+            item(0, "2)", None, "plain"),
+        ]
+    );
+}
+
+#[test]
+fn wiki_links_show_their_label() {
+    assert_eq!(
+        parse_inline("see [[Plans#Q3|the plans]] and [[Budget]]"),
+        vec![
+            plain("see "),
+            styled(
+                "the plans",
+                Style {
+                    link: Some(Link::Note("Plans".to_string())),
+                    ..Style::default()
+                }
+            ),
+            plain(" and "),
+            styled(
+                "Budget",
+                Style {
+                    link: Some(Link::Note("Budget".to_string())),
+                    ..Style::default()
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn url_links_can_hold_emphasis() {
+    let link = Style {
+        link: Some(Link::Url("https://example.com".to_string())),
+        ..Style::default()
+    };
+    assert_eq!(
+        parse_inline("[the **site**](https://example.com)"),
+        vec![
+            styled("the ", link.clone()),
+            styled("site", Style { bold: true, ..link }),
+        ]
+    );
+}
+
+#[test]
+fn backslashes_escape_punctuation_only() {
+    assert_eq!(
+        parse_inline(r"\*not italic\* and \[[not a link]]"),
+        vec![plain("*not italic* and [[not a link]]")]
+    );
+    assert_eq!(
+        parse_inline(r"C:\Users\notes"),
+        vec![plain(r"C:\Users\notes")]
+    );
+}
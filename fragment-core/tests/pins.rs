@@ -0,0 +1,122 @@
+mod common;
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use fragment_core::pins::{self, Pins, Recent};
+use fragment_core::title::TitleStrategy;
+use fragment_core::Note;
+
+use common::{titles, Notes};
+
+fn note<'a>(notes: &'a [Note], title: &str) -> &'a Note {
+    notes
+        .iter()
+        .find(|note| note.title.as_ref() == title)
+        .unwrap()
+}
+
+#[test]
+fn pinned_notes_go_first_keeping_the_sort() {
+    let notes = Notes::new();
+    notes
+        .write_aged("a.md", "", 40)
+        .write_aged("b.md", "", 30)
+        .write_aged("sub/c.md", "", 20)
+        .write_aged("d.md", "", 10);
+    let mut list = notes.list(TitleStrategy::FileName);
+
+    let mut pinned = Pins::default();
+    assert!(pinned.toggle(notes.root(), note(&list, "a")));
+    assert!(pinned.toggle(notes.root(), note(&list, "c")));
+    pinned.pinned_first(notes.root(), &mut list);
+
+    assert_eq!(titles(&list), vec!["c", "a", "d", "b"]);
+    assert!(pinned.is_pinned(notes.root(), note(&list, "c")));
+    assert!(!pinned.toggle(notes.root(), note(&list, "a")));
+    assert!(!pinned.is_pinned(notes.root(), note(&list, "a")));
+}
+
+#[test]
+fn pins_are_kept_relative_to_the_root() {
+    let notes = Notes::new();
+    notes.write("sub/c.md", "").write("d.md", "");
+    let list = notes.list(TitleStrategy::FileName);
+
+    let mut pinned = Pins::default();
+    pinned.toggle(notes.root(), note(&list, "c"));
+    pinned.save(notes.root()).unwrap();
+
+    let saved = std::fs::read_to_string(notes.path(".fragment/pinned.toml")).unwrap();
+    assert_eq!(
+        saved,
+        format!(
+            "pinned = [\n    \"{}\",\n]\n",
+            std::path::Path::new("sub").join("c.md").display()
+        )
+    );
+    assert_eq!(Pins::load(notes.root()), pinned);
+}
+
+#[test]
+fn hand_written_pins_can_be_on_one_line() {
+    let notes = Notes::new();
+    notes
+        .write("a.md", "")
+        .write("b.md", "")
+        .write("c.md", "")
+        .write(
+            ".fragment/pinned.toml",
+            "# Keep these handy\npinned = [\"a.md\", \"b.md\"]\n",
+        );
+    let list = notes.list(TitleStrategy::FileName);
+
+    let pinned = Pins::load(notes.root());
+    assert!(pinned.is_pinned(notes.root(), note(&list, "a")));
+    assert!(pinned.is_pinned(notes.root(), note(&list, "b")));
+    assert!(!pinned.is_pinned(notes.root(), note(&list, "c")));
+}
+
+#[test]
+fn recent_notes_are_most_recent_first_and_skip_missing_ones() {
+    let notes = Notes::new();
+    notes
+        .write("a.md", "")
+        .write("b.md", "")
+        .write("gone.md", "");
+    let list = notes.list(TitleStrategy::FileName);
+    let at = |seconds| UNIX_EPOCH + Duration::from_secs(seconds);
+
+    let mut recent = Recent::default();
+    recent.record(notes.root(), note(&list, "a"), at(100));
+    recent.record(notes.root(), note(&list, "gone"), at(200));
+    recent.record(notes.root(), note(&list, "b"), at(300));
+    recent.record(notes.root(), note(&list, "a"), at(400));
+    recent.save(notes.root()).unwrap();
+
+    std::fs::remove_file(notes.path("gone.md")).unwrap();
+    let list = notes.list(TitleStrategy::FileName);
+    let recent = Recent::load(notes.root());
+    assert_eq!(titles(&recent.notes(notes.root(), &list)), vec!["a", "b"]);
+}
+
+#[test]
+fn only_so_many_recent_notes_are_kept() {
+    let notes = Notes::new();
+    for n in 0..pins::RECENT_LIMIT + 5 {
+        notes.write(&format!("{}.md", n), "");
+    }
+    let list = notes.list(TitleStrategy::FileName);
+
+    let mut recent = Recent::default();
+    for n in 0..pins::RECENT_LIMIT + 5 {
+        let time = UNIX_EPOCH + Duration::from_secs(n as u64);
+        recent.record(notes.root(), note(&list, &n.to_string()), time);
+    }
+
+    let opened = recent.notes(notes.root(), &list);
+    assert_eq!(opened.len(), pins::RECENT_LIMIT);
+    assert_eq!(
+        opened[0].title.as_ref(),
+        (pins::RECENT_LIMIT + 4).to_string()
+    );
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use fragment_core::query::{Filter, ParsedQuery};
+use fragment_core::title::TitleStrategy;
+
+use common::{titles, Notes};
+
+fn filter(key: &str, value: &str) -> Filter {
+    Filter {
+        key: key.to_string(),
+        value: value.to_string(),
+    }
+}
+
+#[test]
+fn filters_are_split_from_the_pattern() {
+    let query = ParsedQuery::parse("Tag:Work  meeting notes has:todo links:\"Big Plans\"");
+    assert_eq!(query.pattern, "meeting notes");
+    assert_eq!(
+        query.filters,
+        vec![
+            filter("tag", "work"),
+            filter("has", "todo"),
+            filter("links", "big plans")
+        ]
+    );
+}
+
+#[test]
+fn front_matter_keys_are_filters_only_if_a_note_has_them() {
+    let notes = Notes::new();
+    notes.write("work.md", "---\nStatus: In Progress\n---\n");
+    let list = notes.list(TitleStrategy::FileName);
+
+    let query = ParsedQuery::parse_for("status:\"In Progress\" Note:draft", &list);
+    assert_eq!(query.pattern, "Note:draft");
+    assert_eq!(query.filters, vec![filter("status", "in progress")]);
+
+    let query = ParsedQuery::parse("status:done");
+    assert_eq!(query.pattern, "status:done");
+    assert!(query.filters.is_empty());
+}
+
+#[test]
+fn things_that_only_look_like_filters_stay_in_the_pattern() {
+    for pattern in &[
+        "https://example.com",
+        "key:",
+        ":value",
+        "a.b:c",
+        "\"note: title\"",
+        "std::fs",
+        "10:30",
+        "Note:draft",
+    ] {
+        let query = ParsedQuery::parse(pattern);
+        assert_eq!(&query.pattern, pattern);
+        assert!(query.filters.is_empty(), "{}", pattern);
+    }
+}
+
+#[test]
+fn filters_check_the_index() {
+    let notes = Notes::new();
+    notes
+        .write_aged(
+            "work.md",
+            "---\nstatus: In Progress\n---\n#work/meetings\n[[Plans]]\n- [ ] call\n",
+            30,
+        )
+        .write_aged("home.md", "#home\n- [x] dishes\n", 20)
+        .write_aged("other.md", "[[Other]]\n", 10);
+    let list = notes.list(TitleStrategy::FileName);
+    let matching = |query: &str| {
+        let query = ParsedQuery::parse_for(query, &list);
+        let found: Vec<_> = list
+            .iter()
+            .filter(|note| query.matches(note))
+            .cloned()
+            .collect();
+        titles(&found)
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(matching("tag:work"), vec!["work"]);
+    assert_eq!(matching("tag:#work/meetings"), vec!["work"]);
+    assert!(matching("tag:wor").is_empty());
+    assert_eq!(matching("links:plans"), vec!["work"]);
+    assert_eq!(matching("has:todo"), vec!["work"]);
+    assert_eq!(matching("has:done"), vec!["home"]);
+    assert_eq!(matching("status:progress"), vec!["work"]);
+    assert!(matching("tag:work has:done").is_empty());
+    assert_eq!(matching("anything").len(), 3);
+}
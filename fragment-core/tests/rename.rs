@@ -0,0 +1,149 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use fragment_core::rename::{self, RenameError};
+use fragment_core::title::TitleStrategy;
+use fragment_core::undo::UndoAction;
+use fragment_core::Note;
+
+use common::Notes;
+
+fn note<'a>(notes: &'a [Note], title: &str) -> &'a Note {
+    notes
+        .iter()
+        .find(|note| note.title.as_ref() == title)
+        .unwrap()
+}
+
+#[test]
+fn dots_in_the_new_title_are_kept() {
+    let notes = Notes::new();
+    notes.write("meeting.md", "");
+    let list = notes.list(TitleStrategy::FileName);
+
+    let plan = rename::plan(
+        Path::new(notes.root()),
+        note(&list, "meeting"),
+        "Meeting 2024.03.01",
+        &list,
+    )
+    .unwrap();
+    assert_eq!(plan.to, notes.path("Meeting 2024.03.01.md"));
+    plan.apply().unwrap();
+    assert!(notes.path("Meeting 2024.03.01.md").exists());
+    assert!(!notes.path("meeting.md").exists());
+}
+
+#[test]
+fn links_in_referring_notes_are_rewritten() {
+    let notes = Notes::new();
+    notes
+        .write("old.md", "See [[old#top]] for this note.\n")
+        .write(
+            "sub/referrer.md",
+            "[[old]], [[Old|a label]], [[old#Heading]], [[older]] and [link](../old.md#part)\n",
+        )
+        .write("unrelated.md", "[[other]]\n");
+    let list = notes.list(TitleStrategy::FileName);
+
+    let plan = rename::plan(Path::new(notes.root()), note(&list, "old"), "new", &list).unwrap();
+    assert_eq!(plan.edits.len(), 2);
+    plan.apply().unwrap();
+
+    assert_eq!(
+        fs::read_to_string(notes.path("sub/referrer.md")).unwrap(),
+        "[[new]], [[new|a label]], [[new#Heading]], [[older]] and [link](../new.md#part)\n"
+    );
+    // Links in the renamed note itself end up in its new file
+    assert_eq!(
+        fs::read_to_string(notes.path("new.md")).unwrap(),
+        "See [[new#top]] for this note.\n"
+    );
+    assert_eq!(
+        fs::read_to_string(notes.path("unrelated.md")).unwrap(),
+        "[[other]]\n"
+    );
+}
+
+#[test]
+fn nothing_changes_if_a_referrer_was_edited_since_planning() {
+    let notes = Notes::new();
+    notes.write("old.md", "").write("referrer.md", "[[old]]\n");
+    let list = notes.list(TitleStrategy::FileName);
+
+    let plan = rename::plan(Path::new(notes.root()), note(&list, "old"), "new", &list).unwrap();
+    notes.write("referrer.md", "[[old]] and more\n");
+
+    assert!(matches!(plan.apply(), Err(RenameError::Changed(_))));
+    assert!(notes.path("old.md").exists());
+    assert!(!notes.path("new.md").exists());
+}
+
+#[test]
+fn undoing_a_rename_puts_everything_back() {
+    let notes = Notes::new();
+    notes
+        .write("old.md", "[[old]]\n")
+        .write("referrer.md", "[[old|label]]\n");
+    let list = notes.list(TitleStrategy::FileName);
+
+    let plan = rename::plan(Path::new(notes.root()), note(&list, "old"), "new", &list).unwrap();
+    plan.apply().unwrap();
+    UndoAction::Rename(plan).undo().unwrap();
+
+    assert!(!notes.path("new.md").exists());
+    assert_eq!(
+        fs::read_to_string(notes.path("old.md")).unwrap(),
+        "[[old]]\n"
+    );
+    assert_eq!(
+        fs::read_to_string(notes.path("referrer.md")).unwrap(),
+        "[[old|label]]\n"
+    );
+}
+
+#[test]
+fn undoing_a_duplicate_keeps_an_edited_copy() {
+    let notes = Notes::new();
+    notes.write("v1.2.md", "original\n");
+
+    let copy = rename::duplicate(&notes.path("v1.2.md")).unwrap();
+    assert_eq!(copy, notes.path("v1.2 copy.md"));
+    let undo = UndoAction::Duplicate {
+        original: notes.path("v1.2.md"),
+        copy: copy.clone(),
+        contents: fs::read(&copy).unwrap(),
+    };
+
+    notes.write("v1.2 copy.md", "edited\n");
+    assert!(matches!(undo.undo(), Err(RenameError::Changed(_))));
+    assert!(copy.exists());
+
+    notes.write("v1.2 copy.md", "original\n");
+    undo.undo().unwrap();
+    assert!(!copy.exists());
+    assert!(notes.path("v1.2.md").exists());
+}
+
+#[test]
+fn org_links_are_rewritten_too() {
+    let notes = Notes::new();
+    notes.write("old.org", "").write(
+        "sub/referrer.org",
+        "[[file:../old.org][the old note]], [[file:../old.org::*Tasks]], [[old]] and [[file:../older.org]]\n",
+    );
+    let list = notes.list(TitleStrategy::FileName);
+    let old = note(&list, "old");
+
+    let referrers = rename::referrers(old, &list);
+    assert_eq!(referrers.len(), 1);
+    let plan = rename::plan(Path::new(notes.root()), old, "new", &list).unwrap();
+    plan.apply().unwrap();
+
+    assert_eq!(
+        fs::read_to_string(notes.path("sub/referrer.org")).unwrap(),
+        "[[file:../new.org][the old note]], [[file:../new.org::*Tasks]], [[new]] and [[file:../older.org]]\n"
+    );
+}
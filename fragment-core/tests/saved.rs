@@ -0,0 +1,84 @@
+mod common;
+
+use std::sync::Mutex;
+
+use fragment_core::index::Index;
+use fragment_core::saved::{self, SavedSearch};
+use fragment_core::title::TitleStrategy;
+
+use common::Notes;
+
+fn saved(name: &str, query: &str) -> SavedSearch {
+    SavedSearch {
+        name: name.to_string(),
+        query: query.to_string(),
+    }
+}
+
+#[test]
+fn no_file_means_no_saved_searches() {
+    let notes = Notes::new();
+    assert!(saved::load(notes.root()).is_empty());
+}
+
+#[test]
+fn saved_searches_round_trip_in_order() {
+    let notes = Notes::new();
+    let searches = vec![
+        saved("Inbox", "tag:inbox"),
+        saved("Links to \"Plan\"", r#"links:"Plan" \d+"#),
+        saved("Todo = open", "has:todo"),
+    ];
+
+    saved::save(notes.root(), &searches).unwrap();
+    assert_eq!(saved::load(notes.root()), searches);
+}
+
+#[test]
+fn control_characters_are_escaped_as_toml() {
+    let notes = Notes::new();
+    let searches = vec![saved("Two\nlines", "tab\there \u{1}")];
+
+    saved::save(notes.root(), &searches).unwrap();
+    let contents = std::fs::read_to_string(notes.path(".fragment/searches.toml")).unwrap();
+    assert_eq!(contents, "\"Two\\nlines\" = \"tab\\there \\u0001\"\n");
+    assert_eq!(saved::load(notes.root()), searches);
+}
+
+#[test]
+fn hand_written_files_can_have_comments() {
+    let notes = Notes::new();
+    notes.write(
+        ".fragment/searches.toml",
+        "# Daily\n\"Inbox\" = \"tag:inbox\"\n\nnot a search\n\"Todo\"=\"has:todo\"\n",
+    );
+
+    assert_eq!(
+        saved::load(notes.root()),
+        vec![saved("Inbox", "tag:inbox"), saved("Todo", "has:todo")]
+    );
+}
+
+#[test]
+fn counts_run_every_search() {
+    let notes = Notes::new();
+    notes
+        .write("a.md", "#inbox\n- [ ] reply")
+        .write("b.md", "#inbox")
+        .write("c.md", "- [x] done");
+
+    let searches = vec![
+        saved("Inbox", "tag:inbox"),
+        saved("Todo", "has:todo"),
+        saved("Broken", "(unclosed"),
+    ];
+    assert_eq!(
+        saved::counts(
+            notes.root(),
+            TitleStrategy::FileName,
+            &Mutex::new(Index::new()),
+            &searches
+        ),
+        vec![Some(2), Some(1), None]
+    );
+}
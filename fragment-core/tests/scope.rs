@@ -0,0 +1,147 @@
+mod common;
+
+use std::sync::atomic::AtomicU64;
+use std::sync::Mutex;
+
+use chrono::{Duration, Local};
+use fragment_core::index::Index;
+use fragment_core::scope::Scope;
+use fragment_core::search::{self, SortMethod};
+use fragment_core::title::TitleStrategy;
+
+use common::{relative_paths, Notes};
+
+fn list(notes: &Notes, scope: &Scope) -> Vec<String> {
+    let list = search::list_files_in(
+        notes.root(),
+        scope,
+        SortMethod::DateNewest,
+        TitleStrategy::FileName,
+        &mut Index::new(),
+        &AtomicU64::new(0),
+        1,
+    );
+    let mut paths = relative_paths(notes.root(), &list);
+    paths.sort();
+    paths
+}
+
+fn sample() -> Notes {
+    let notes = Notes::new();
+    notes
+        .write_aged("inbox.md", "", 0)
+        .write_aged("work/plan.org", "", 3 * 24 * 60 * 60)
+        .write_aged("work/meetings/monday.md", "", 10 * 24 * 60 * 60)
+        .write_aged("work.md", "", 0)
+        .write_aged("reading/books.txt", "", 40 * 24 * 60 * 60);
+    notes
+}
+
+#[test]
+fn the_default_scope_is_everything() {
+    let notes = sample();
+    assert_eq!(list(&notes, &Scope::default()).len(), 5);
+}
+
+#[test]
+fn folders_take_their_subfolders_but_not_similar_names() {
+    let notes = sample();
+    let scope = Scope {
+        folder: Some("work".to_string()),
+        ..Scope::default()
+    };
+    assert_eq!(
+        list(&notes, &scope),
+        vec!["work/meetings/monday.md", "work/plan.org"]
+    );
+}
+
+#[test]
+fn extensions_ignore_case() {
+    let notes = sample();
+    notes.write("LOUD.MD", "");
+    let scope = Scope {
+        extensions: vec!["md".to_string(), "txt".to_string()],
+        ..Scope::default()
+    };
+    assert_eq!(
+        list(&notes, &scope),
+        vec![
+            "LOUD.MD",
+            "inbox.md",
+            "reading/books.txt",
+            "work.md",
+            "work/meetings/monday.md"
+        ]
+    );
+}
+
+#[test]
+fn dates_are_whole_days_at_both_ends() {
+    let notes = sample();
+    let today = Local::now().naive_local().date();
+    let scope = Scope {
+        modified_after: Some(today - Duration::days(10)),
+        modified_before: Some(today - Duration::days(3)),
+        ..Scope::default()
+    };
+    assert_eq!(
+        list(&notes, &scope),
+        vec!["work/meetings/monday.md", "work/plan.org"]
+    );
+}
+
+#[test]
+fn scoped_searches_only_match_in_scope() {
+    let notes = Notes::new();
+    notes
+        .write("work/a.md", "needle")
+        .write("home/b.md", "needle");
+    let scope = Scope {
+        folder: Some("home".to_string()),
+        ..Scope::default()
+    };
+
+    let results = search::search_in(
+        "needle",
+        notes.root(),
+        &scope,
+        TitleStrategy::FileName,
+        &Mutex::new(Index::new()),
+        &AtomicU64::new(0),
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        relative_paths(notes.root(), &results.matches),
+        vec!["home/b.md"]
+    );
+    assert_eq!(results.notes.len(), 1);
+}
+
+#[test]
+fn folders_count_notes_in_their_subfolders() {
+    let notes = sample();
+    notes
+        .write("work b/x.md", "")
+        .write(".git/HEAD", "")
+        .write("empty/.keep", "");
+
+    let folders: Vec<(String, usize)> = search::folders(notes.root())
+        .into_iter()
+        .map(|folder| (folder.path, folder.notes))
+        .collect();
+    let expected = vec![
+        ("", 6),
+        ("empty", 0),
+        ("reading", 1),
+        ("work", 2),
+        ("work/meetings", 1),
+        ("work b", 1),
+    ];
+    let expected: Vec<(String, usize)> = expected
+        .into_iter()
+        .map(|(path, count)| (path.to_string(), count))
+        .collect();
+    assert_eq!(folders, expected);
+}
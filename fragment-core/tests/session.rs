@@ -0,0 +1,45 @@
+mod common;
+
+use fragment_core::search::SortMethod;
+use fragment_core::session::Session;
+
+use common::Notes;
+
+#[test]
+fn a_missing_state_file_means_defaults() {
+    let notes = Notes::new();
+    assert_eq!(Session::load(notes.root()), Session::default());
+}
+
+#[test]
+fn sessions_round_trip() {
+    let notes = Notes::new();
+    let session = Session {
+        window_size: Some((1280.0, 720.5)),
+        window_position: Some((-40.0, 25.0)),
+        split_point: 0.65,
+        sort: SortMethod::TitleZA,
+        query: r#"links:"Plan" \d+"#.to_string(),
+        selected: Some(notes.path("a b.md").display().to_string()),
+    };
+
+    session.save(notes.root()).unwrap();
+    assert_eq!(Session::load(notes.root()), session);
+}
+
+#[test]
+fn bad_values_keep_their_defaults() {
+    let notes = Notes::new();
+    notes.write(
+        ".fragment/state.toml",
+        "window_size = [800]\nsplit_point = 3\nsort = \"sideways\"\nquery = \"meeting\"\n",
+    );
+
+    assert_eq!(
+        Session::load(notes.root()),
+        Session {
+            query: "meeting".to_string(),
+            ..Session::default()
+        }
+    );
+}
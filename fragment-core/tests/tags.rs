@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use fragment_core::frontmatter;
+use fragment_core::tags;
+
+fn extract(contents: &str) -> Vec<String> {
+    let (front_matter, body) = frontmatter::parse(contents);
+    tags::extract(&front_matter, body)
+        .iter()
+        .map(|tag| tag.to_string())
+        .collect()
+}
+
+#[test]
+fn inline_tags_come_before_front_matter_ones() {
+    assert_eq!(
+        extract("---\ntags: [meta, '#inline']\n---\nSome #inline text, #project/alpha.\n"),
+        vec!["inline", "project/alpha", "meta"]
+    );
+}
+
+#[test]
+fn headings_numbers_and_code_arent_tags() {
+    let contents = "# Heading\n## Sub\nIssue #123 and a#b\n```\n#include <stdio.h>\n```\n#real\n";
+    assert_eq!(extract(contents), vec!["real"]);
+}
+
+#[test]
+fn nested_tags_are_within_their_parents() {
+    assert!(tags::is_within("Project/Alpha", "project"));
+    assert!(tags::is_within("project", "PROJECT"));
+    assert!(!tags::is_within("projects", "project"));
+    assert!(!tags::is_within("project", "project/alpha"));
+}
+
+#[test]
+fn counts_include_parents_once_per_note() {
+    let notes: Vec<Vec<Arc<str>>> = vec![
+        vec!["project/alpha".into(), "project/beta".into()],
+        vec!["project".into(), "home".into()],
+    ];
+    let counts = tags::count(notes.iter().map(Vec::as_slice));
+    let counts: Vec<(&str, usize)> = counts
+        .iter()
+        .map(|(tag, count)| (tag.as_str(), *count))
+        .collect();
+    assert_eq!(
+        counts,
+        vec![
+            ("home", 1),
+            ("project", 2),
+            ("project/alpha", 1),
+            ("project/beta", 1)
+        ]
+    );
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use std::fs;
+use std::io;
+
+use fragment_core::tasks::{self, Task};
+
+use common::Notes;
+
+fn task(line: u64, text: &str, done: bool) -> Task {
+    Task {
+        line,
+        text: text.into(),
+        done,
+    }
+}
+
+#[test]
+fn checkboxes_are_found_outside_code() {
+    let contents = "- [ ] open\n  * [x] done\n3. [X] numbered\n+ [-] partly\n\
+                    - [] no\n- [ ]no space\n```\n- [ ] example\n```\n";
+    assert_eq!(
+        tasks::extract(contents),
+        vec![
+            task(1, "open", false),
+            task(2, "done", true),
+            task(3, "numbered", true),
+            task(4, "partly", false),
+        ]
+    );
+}
+
+#[test]
+fn toggling_ticks_and_unticks() {
+    let notes = Notes::new();
+    notes.write("todo.md", "# Todo\n- [ ] write tests\n- [ ] other");
+    let path = notes.path("todo.md");
+
+    tasks::toggle(&path, &task(2, "write tests", false)).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# Todo\n- [x] write tests\n- [ ] other"
+    );
+    tasks::toggle(&path, &task(2, "write tests", true)).unwrap();
+    // The last line has no newline to find its end by
+    tasks::toggle(&path, &task(3, "other", false)).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# Todo\n- [ ] write tests\n- [x] other"
+    );
+}
+
+#[test]
+fn crlf_line_endings_are_kept() {
+    let notes = Notes::new();
+    notes.write("todo.md", "- [ ] one\r\n- [x] two\r\n");
+    let path = notes.path("todo.md");
+
+    let found = tasks::extract(&fs::read_to_string(&path).unwrap());
+    assert_eq!(found, vec![task(1, "one", false), task(2, "two", true)]);
+    tasks::toggle(&path, &found[1]).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "- [ ] one\r\n- [ ] two\r\n"
+    );
+}
+
+#[test]
+fn tasks_that_moved_or_changed_are_left_alone() {
+    let notes = Notes::new();
+    notes.write("todo.md", "- [ ] one\n- [x] two\n");
+    let path = notes.path("todo.md");
+
+    for stale in &[
+        task(10, "one", false),
+        task(1, "one", true),
+        task(1, "renamed", false),
+        task(2, "one", false),
+    ] {
+        let err = tasks::toggle(&path, stale).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", stale);
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), "- [ ] one\n- [x] two\n");
+}
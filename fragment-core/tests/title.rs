@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use fragment_core::format::{self, Markdown, NoteFormat, Org};
+use fragment_core::title::TitleStrategy;
+
+fn title(strategy: TitleStrategy, name: &str, contents: &str) -> String {
+    let path = Path::new(name);
+    let format = format::for_path(path);
+    let (front_matter, body) = format.split_metadata(contents);
+    strategy.title(path, format, &front_matter, body)
+}
+
+#[test]
+fn strategies_parse_from_their_settings_names() {
+    assert_eq!("filename".parse(), Ok(TitleStrategy::FileName));
+    assert_eq!("frontmatter".parse(), Ok(TitleStrategy::FrontMatter));
+    assert_eq!("heading".parse(), Ok(TitleStrategy::Heading));
+    assert_eq!("firstline".parse(), Ok(TitleStrategy::FirstLine));
+    assert!("title".parse::<TitleStrategy>().is_err());
+}
+
+#[test]
+fn each_strategy_finds_its_title() {
+    let contents = "---\ntitle: From front matter\n---\n\nIntro line\n\n# From heading\n";
+    assert_eq!(
+        title(TitleStrategy::FileName, "notes/my note.md", contents),
+        "my note"
+    );
+    assert_eq!(
+        title(TitleStrategy::FrontMatter, "note.md", contents),
+        "From front matter"
+    );
+    assert_eq!(
+        title(TitleStrategy::Heading, "note.md", contents),
+        "From heading"
+    );
+    assert_eq!(
+        title(TitleStrategy::FirstLine, "note.md", contents),
+        "Intro line"
+    );
+}
+
+#[test]
+fn org_notes_use_their_keywords_and_headings() {
+    let contents = "#+TITLE: Org title\n\n* Org heading\n";
+    assert_eq!(
+        title(TitleStrategy::FrontMatter, "note.org", contents),
+        "Org title"
+    );
+    assert_eq!(
+        title(TitleStrategy::Heading, "note.org", contents),
+        "Org heading"
+    );
+}
+
+#[test]
+fn missing_titles_fall_back_to_the_file_name() {
+    for &strategy in &[
+        TitleStrategy::FrontMatter,
+        TitleStrategy::Heading,
+        TitleStrategy::FirstLine,
+    ] {
+        assert_eq!(
+            title(strategy, "plain.md", "\n\n"),
+            "plain",
+            "{:?}",
+            strategy
+        );
+    }
+    assert_eq!(
+        title(TitleStrategy::FrontMatter, "empty.md", "---\ntitle:\n---\n"),
+        "empty"
+    );
+    // Only a level one heading counts
+    assert_eq!(
+        title(TitleStrategy::Heading, "sub.md", "## Section\n"),
+        "sub"
+    );
+}
+
+#[test]
+fn new_note_contents_match_the_format() {
+    assert_eq!(
+        TitleStrategy::Heading.new_note_contents(&Markdown, "Plans"),
+        "# Plans\n\n"
+    );
+    assert_eq!(
+        TitleStrategy::FrontMatter.new_note_contents(&Org, "Plans"),
+        "#+TITLE: Plans\n\n"
+    );
+    assert_eq!(
+        TitleStrategy::FileName.new_note_contents(&Markdown, "Plans"),
+        ""
+    );
+    assert_eq!(Markdown.heading_line("Plans"), "# Plans\n\n");
+}
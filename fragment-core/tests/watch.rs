@@ -0,0 +1,308 @@
+mod common;
+
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use fragment_core::scope::Scope;
+use fragment_core::search;
+use fragment_core::title::TitleStrategy;
+use fragment_core::watch::{self, Batch, Change, Snapshot};
+use fragment_core::Note;
+
+use common::{relative_paths, Notes};
+
+fn change(notes: &Notes, relative: &[&str]) -> Change {
+    Change {
+        paths: relative.iter().map(|path| notes.path(path)).collect(),
+    }
+}
+
+fn paths(notes: &Notes, relative: &[&str]) -> Vec<PathBuf> {
+    relative.iter().map(|path| notes.path(path)).collect()
+}
+
+#[test]
+fn a_burst_of_events_coalesces_by_what_is_on_disk() {
+    let notes = Notes::new();
+    notes.write("saved.md", "").write("renamed to.md", "");
+
+    // An editor's atomic save, a rename and a file that came and went
+    let changes = vec![
+        change(&notes, &["saved.md.tmp"]),
+        change(&notes, &["saved.md.tmp", "saved.md"]),
+        change(&notes, &["saved.md"]),
+        change(&notes, &["renamed from.md", "renamed to.md"]),
+        change(&notes, &["fleeting.md"]),
+        change(&notes, &["fleeting.md"]),
+    ];
+
+    let batch = Batch::from_changes(notes.root(), &changes);
+    let changed: Vec<_> = batch.changed.iter().cloned().collect();
+    let removed: Vec<_> = batch.removed.iter().cloned().collect();
+    assert_eq!(changed, paths(&notes, &["renamed to.md", "saved.md"]));
+    assert_eq!(
+        removed,
+        paths(&notes, &["fleeting.md", "renamed from.md", "saved.md.tmp"])
+    );
+}
+
+#[test]
+fn hidden_and_outside_paths_are_ignored() {
+    let notes = Notes::new();
+    notes
+        .write(".git/index", "")
+        .write("folder/.note.md.swp", "");
+
+    let changes = vec![
+        change(&notes, &[".git/index"]),
+        change(&notes, &["folder/.note.md.swp"]),
+        change(&notes, &[""]),
+        Change {
+            paths: vec![PathBuf::from("/somewhere/else.md")],
+        },
+    ];
+
+    assert!(Batch::from_changes(notes.root(), &changes).is_empty());
+}
+
+#[test]
+fn debouncing_waits_for_the_changes_to_go_quiet() {
+    let notes = Notes::new();
+    notes.write("a.md", "").write("b.md", "");
+
+    let (sender, receiver) = mpsc::channel();
+    for _ in 0..5 {
+        sender.send(change(&notes, &["a.md"])).unwrap();
+    }
+    sender.send(change(&notes, &["b.md"])).unwrap();
+    drop(sender);
+
+    let mut batches = Vec::new();
+    watch::debounce(
+        &receiver,
+        notes.root(),
+        Duration::from_millis(50),
+        Duration::from_secs(5),
+        |batch| batches.push(batch),
+    );
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].changed.len(), 2);
+}
+
+#[test]
+fn changes_after_a_quiet_period_are_a_new_batch() {
+    let notes = Notes::new();
+    notes.write("a.md", "");
+
+    let (sender, receiver) = mpsc::channel();
+    let path = notes.path("a.md");
+    let source = thread::spawn(move || {
+        for _ in 0..2 {
+            sender
+                .send(Change {
+                    paths: vec![path.clone()],
+                })
+                .unwrap();
+            thread::sleep(Duration::from_millis(200));
+        }
+    });
+
+    let mut batches = 0;
+    watch::debounce(
+        &receiver,
+        notes.root(),
+        Duration::from_millis(50),
+        Duration::from_secs(5),
+        |_| batches += 1,
+    );
+    source.join().unwrap();
+    assert_eq!(batches, 2);
+}
+
+#[test]
+fn changes_that_never_go_quiet_are_applied_anyway() {
+    let notes = Notes::new();
+    notes.write("a.md", "");
+
+    let (sender, receiver) = mpsc::channel();
+    let path = notes.path("a.md");
+    let source = thread::spawn(move || {
+        // A change every 20ms for half a second, never quiet for 50ms
+        for _ in 0..25 {
+            sender
+                .send(Change {
+                    paths: vec![path.clone()],
+                })
+                .unwrap();
+            thread::sleep(Duration::from_millis(20));
+        }
+    });
+
+    let mut batches = 0;
+    watch::debounce(
+        &receiver,
+        notes.root(),
+        Duration::from_millis(50),
+        Duration::from_millis(100),
+        |_| batches += 1,
+    );
+    source.join().unwrap();
+    assert!(batches >= 3, "{} batches", batches);
+}
+
+fn apply(
+    notes: &Notes,
+    query: &str,
+    changes: &[Change],
+    before: &search::SearchResults,
+) -> (Vec<Note>, Vec<Note>) {
+    let batch = Batch::from_changes(notes.root(), changes);
+    search::apply_changes(
+        query,
+        notes.root(),
+        &Scope::default(),
+        TitleStrategy::FileName,
+        &batch,
+        &before.notes,
+        &before.matches,
+    )
+    .expect("Update failed")
+}
+
+#[test]
+fn updates_match_a_fresh_search() {
+    let notes = Notes::new();
+    notes
+        .write_aged("kept.md", "needle", 50)
+        .write_aged("edited.md", "needle", 40)
+        .write_aged("deleted.md", "needle", 30)
+        .write_aged("moved.md", "needle", 20);
+    let before = notes.search("needle");
+
+    std::fs::write(notes.path("edited.md"), "no longer").unwrap();
+    std::fs::remove_file(notes.path("deleted.md")).unwrap();
+    std::fs::create_dir(notes.path("archive")).unwrap();
+    std::fs::rename(notes.path("moved.md"), notes.path("archive/moved.md")).unwrap();
+    notes.write_aged("created.md", "a new needle", 10);
+
+    let changes = vec![
+        change(&notes, &["edited.md"]),
+        change(&notes, &["deleted.md"]),
+        change(&notes, &["archive"]),
+        change(&notes, &["moved.md", "archive/moved.md"]),
+        change(&notes, &["created.md"]),
+    ];
+    let (all, matches) = apply(&notes, "needle", &changes, &before);
+
+    let after = notes.search("needle");
+    // Matches are sorted by whoever shows them, so only what's in them counts
+    let sorted = |list: &[Note]| {
+        let mut paths = relative_paths(notes.root(), list);
+        paths.sort();
+        paths
+    };
+    assert_eq!(sorted(&matches), sorted(&after.matches));
+    assert_eq!(
+        relative_paths(notes.root(), &all),
+        relative_paths(notes.root(), &after.notes)
+    );
+}
+
+#[test]
+fn removing_a_folder_drops_its_notes() {
+    let notes = Notes::new();
+    notes
+        .write("projects/a.md", "")
+        .write("projects/deep/b.md", "")
+        .write("other.md", "");
+    let before = notes.search("");
+
+    std::fs::remove_dir_all(notes.path("projects")).unwrap();
+    let changes = vec![change(&notes, &["projects"])];
+    let (all, matches) = apply(&notes, "", &changes, &before);
+
+    assert_eq!(relative_paths(notes.root(), &all), vec!["other.md"]);
+    assert_eq!(relative_paths(notes.root(), &matches), vec!["other.md"]);
+}
+
+#[test]
+fn untouched_notes_are_not_re_read() {
+    let notes = Notes::new();
+    notes.write("a.md", "one").write("b.md", "two");
+    let before = notes.search("");
+
+    // Changed on disk but not reported, so the old index entry stays
+    std::fs::write(notes.path("a.md"), "one two three").unwrap();
+    std::fs::write(notes.path("b.md"), "two three").unwrap();
+    let changes = vec![change(&notes, &["b.md"])];
+    let (all, _) = apply(&notes, "", &changes, &before);
+
+    let word_count = |name: &str| {
+        all.iter()
+            .find(|note| note.file_name.as_ref() == name)
+            .map(|note| note.word_count)
+    };
+    assert_eq!(word_count("a.md"), Some(1));
+    assert_eq!(word_count("b.md"), Some(2));
+}
+
+fn changed_paths(changes: Vec<Change>, notes: &Notes) -> Vec<String> {
+    let mut paths: Vec<String> = changes
+        .into_iter()
+        .flat_map(|change| change.paths)
+        .map(|path| {
+            let path = path.strip_prefix(notes.root()).unwrap();
+            path.display().to_string()
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn snapshots_see_creates_edits_and_removes() {
+    let notes = Notes::new();
+    notes
+        .write_aged("touched.md", "same size", 60)
+        .write("grown.md", "short")
+        .write("same.md", "")
+        .write("gone.md", "");
+    let before = Snapshot::scan(notes.root());
+
+    notes
+        .write_aged("touched.md", "same size", 30)
+        .write("grown.md", "a good deal longer")
+        .write("new.md", "")
+        .write(".git/index", "");
+    std::fs::remove_file(notes.path("gone.md")).unwrap();
+    let after = Snapshot::scan(notes.root());
+
+    assert_eq!(
+        changed_paths(after.changes_since(&before), &notes),
+        vec!["gone.md", "grown.md", "new.md", "touched.md"]
+    );
+    assert!(after.changes_since(&after).is_empty());
+}
+
+#[test]
+fn polling_stops_when_the_root_goes_missing() {
+    let notes = Notes::new();
+    let root = notes.path("notes");
+    notes.write("notes/a.md", "");
+
+    let (sender, receiver) = mpsc::channel();
+    let poller = {
+        let root = root.display().to_string();
+        thread::spawn(move || watch::poll(&root, Duration::from_millis(20), &sender))
+    };
+
+    thread::sleep(Duration::from_millis(50));
+    notes.write("notes/b.md", "");
+    let created = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(created.paths, vec![root.join("b.md")]);
+
+    std::fs::remove_dir_all(&root).unwrap();
+    poller.join().unwrap();
+}
@@ -0,0 +1,303 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use druid::widget::Controller;
+use druid::{
+    Command, ContextMenu, Data, Env, Event, EventCtx, LocalizedString, MenuDesc, MenuItem, Target,
+    Widget,
+};
+use fragment_core::rename::{self, RenameError};
+use fragment_core::undo::UndoAction;
+
+use crate::columns::Columns;
+use crate::delegate::NOTE_ACTION;
+use crate::search::ListItem;
+use crate::FragmentState;
+
+/// Hidden folder under the root that deleted notes are moved into, so a
+/// delete can be undone.
+const TRASH_FOLDER: &str = ".trash";
+
+/// Things that can be done to a note from the results list
+#[derive(Clone, Copy, Data, Debug, PartialEq)]
+pub enum NoteAction {
+    Open,
+    Rename,
+    Delete,
+    Archive,
+    Move,
+    Duplicate,
+    Reveal,
+    Pin,
+}
+
+impl NoteAction {
+    const ALL: [NoteAction; 8] = [
+        NoteAction::Open,
+        NoteAction::Rename,
+        NoteAction::Delete,
+        NoteAction::Archive,
+        NoteAction::Move,
+        NoteAction::Duplicate,
+        NoteAction::Reveal,
+        NoteAction::Pin,
+    ];
+
+    /// What it does, for error messages
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            NoteAction::Open => "open the note",
+            NoteAction::Rename => "rename the note",
+            NoteAction::Delete => "move the note to the trash",
+            NoteAction::Archive => "archive the note",
+            NoteAction::Move => "move the note",
+            NoteAction::Duplicate => "duplicate the note",
+            NoteAction::Reveal => "reveal the note",
+            NoteAction::Pin => "pin or unpin the note",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            NoteAction::Open => "Open in Editor (Ctrl+Shift+O)",
+            NoteAction::Rename => "Rename… (F2)",
+            NoteAction::Delete => "Move to Trash (Ctrl+Shift+Backspace)",
+            NoteAction::Archive => "Archive (Ctrl+Shift+E)",
+            NoteAction::Move => "Move to Folder… (Ctrl+Shift+M)",
+            NoteAction::Duplicate => "Duplicate (Ctrl+Shift+D)",
+            NoteAction::Reveal => "Reveal in File Manager (Ctrl+Shift+R)",
+            NoteAction::Pin => "Pin to Top (Ctrl+Shift+P)",
+        }
+    }
+}
+
+/// The text box shown under the search box while an action needs input
+#[derive(Clone, Copy, Data, Debug, PartialEq)]
+pub enum Prompt {
+    None,
+    Rename,
+    Move,
+    /// Naming the current query to save it
+    SaveSearch,
+}
+
+impl Prompt {
+    /// What confirming it does, for error messages
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Prompt::None => "do that",
+            Prompt::Rename => "rename the note",
+            Prompt::Move => "move the note",
+            Prompt::SaveSearch => "save the search",
+        }
+    }
+}
+
+fn context_menu(item: &ListItem) -> MenuDesc<FragmentState> {
+    let mut menu = MenuDesc::empty();
+    for action in NoteAction::ALL.iter().copied() {
+        let label = match action {
+            NoteAction::Pin if item.pinned => "Unpin (Ctrl+Shift+P)",
+            _ => action.label(),
+        };
+        menu = menu.append(MenuItem::new(
+            LocalizedString::new("fragment-note-action").with_placeholder(label),
+            Command::new(NOTE_ACTION, (action, item.path.clone()), Target::Global),
+        ));
+    }
+    menu
+}
+
+/// Selects a result row and shows its actions when it's right-clicked, and
+/// opens it in the editor when it's double-clicked
+pub struct RowMenu;
+
+impl<W: Widget<(Columns, ListItem)>> Controller<(Columns, ListItem), W> for RowMenu {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut (Columns, ListItem),
+        env: &Env,
+    ) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_right() => {
+                ListItem::preview_note(ctx, &mut data.1, env);
+                ctx.show_context_menu(ContextMenu::new(context_menu(&data.1), mouse.window_pos));
+            }
+            Event::MouseDown(mouse) if mouse.button.is_left() && mouse.count == 2 => {
+                ctx.submit_command(Command::new(
+                    NOTE_ACTION,
+                    (NoteAction::Open, data.1.path.clone()),
+                    Target::Global,
+                ));
+                child.event(ctx, event, data, env);
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
+
+/// File operations on notes, each of which can be undone within the session
+impl FragmentState {
+    pub(crate) fn note_action(
+        &mut self,
+        action: NoteAction,
+        path: &Arc<str>,
+    ) -> Result<(), RenameError> {
+        self.selected_path = Some(path.clone());
+        let note = match self.selected_item() {
+            Some(note) => note.clone(),
+            None => return Ok(()),
+        };
+        let note_path = PathBuf::from(note.path.as_ref());
+        let root = PathBuf::from(&self.path);
+
+        match action {
+            NoteAction::Open => {
+                note.open_note_in_editor(self.config.editor.as_deref())
+                    .map_err(RenameError::Io)?;
+                self.record_recent(path);
+            }
+            NoteAction::Rename => {
+                self.prompt_text = note.title.to_string();
+                self.rename_referrers = Arc::new(rename::referrers(&note, &self.notes));
+                self.prompt = Prompt::Rename;
+            }
+            NoteAction::Move => {
+                self.prompt_text = note.folder.to_string();
+                self.prompt = Prompt::Move;
+            }
+            NoteAction::Delete => {
+                self.move_note(&note_path, &root.join(TRASH_FOLDER), "delete")?;
+            }
+            NoteAction::Archive => {
+                let archive = root.join(&self.config.archive_folder);
+                self.move_note(&note_path, &archive, "archive")?;
+            }
+            NoteAction::Duplicate => {
+                let copy = rename::duplicate(&note_path)?;
+                let contents = std::fs::read(&copy).map_err(RenameError::Io)?;
+                self.status = format!("Duplicated {}", note.title);
+                self.push_undo(UndoAction::Duplicate {
+                    original: note_path,
+                    copy,
+                    contents,
+                });
+            }
+            NoteAction::Reveal => {
+                let folder = note_path.parent().unwrap_or_else(|| root.as_path());
+                open::that(folder).map_err(RenameError::Io)?;
+            }
+            NoteAction::Pin => {
+                self.toggle_pin(&note).map_err(RenameError::Io)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn move_note(
+        &mut self,
+        path: &Path,
+        folder: &Path,
+        verb: &'static str,
+    ) -> Result<(), RenameError> {
+        let to = rename::move_into(Path::new(&self.path), path, folder)?;
+        self.status = format!(
+            "Moved {} to {} (Ctrl+Alt+Z to undo)",
+            display_name(path),
+            folder.display()
+        );
+        self.push_undo(UndoAction::Move {
+            verb,
+            from: path.to_path_buf(),
+            to,
+        });
+        self.selected_path = None;
+        self.selected_note = None;
+        Ok(())
+    }
+
+    pub(crate) fn cancel_prompt(&mut self) {
+        self.prompt = Prompt::None;
+        self.prompt_text.clear();
+        self.rename_referrers = Arc::new(Vec::new());
+    }
+
+    pub(crate) fn confirm_prompt(&mut self) -> Result<(), RenameError> {
+        if self.prompt == Prompt::SaveSearch {
+            let name = self.prompt_text.clone();
+            self.save_search(&name).map_err(RenameError::Io)?;
+            self.cancel_prompt();
+            return Ok(());
+        }
+
+        let note = match self.selected_item() {
+            Some(note) => note.clone(),
+            None => return Ok(()),
+        };
+
+        match self.prompt {
+            Prompt::Rename => {
+                let plan =
+                    rename::plan(Path::new(&self.path), &note, &self.prompt_text, &self.notes)?;
+                plan.apply()?;
+
+                self.status = format!(
+                    "Renamed {} to {}, updating links in {} notes",
+                    note.title,
+                    self.prompt_text.trim(),
+                    plan.edits
+                        .iter()
+                        .filter(|edit| edit.path != plan.from)
+                        .count()
+                );
+                self.selected_path = Some(plan.to.display().to_string().into());
+                self.push_undo(UndoAction::Rename(plan));
+            }
+            Prompt::Move => {
+                let folder = Path::new(&self.path).join(self.prompt_text.trim());
+                self.move_note(Path::new(note.path.as_ref()), &folder, "move")?;
+            }
+            Prompt::SaveSearch | Prompt::None => {}
+        }
+
+        self.cancel_prompt();
+        Ok(())
+    }
+
+    fn push_undo(&mut self, action: UndoAction) {
+        Arc::make_mut(&mut self.undo).push(action);
+    }
+
+    /// Takes back the most recent file change
+    pub(crate) fn undo_last(&mut self) {
+        let action = match Arc::make_mut(&mut self.undo).pop() {
+            Some(action) => action,
+            None => {
+                self.status = "Nothing to undo".to_string();
+                return;
+            }
+        };
+        self.status = match action.undo() {
+            Ok(()) => {
+                // Keep the selection on a note that the undo moved back
+                if let Some((moved_to, restored)) = action.restored() {
+                    let moved_to: Arc<str> = moved_to.display().to_string().into();
+                    if self.selected_path.as_ref() == Some(&moved_to) {
+                        self.selected_path = Some(restored.display().to_string().into());
+                    }
+                }
+                format!("Undid {}", action.description())
+            }
+            Err(err) => format!("Couldn't undo {}: {}", action.description(), err),
+        };
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
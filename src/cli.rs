@@ -0,0 +1,368 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::AtomicU64;
+use std::time::SystemTime;
+
+use argh::FromArgs;
+use chrono::{DateTime, Local};
+use grep::cli;
+use grep::matcher::Matcher;
+use grep::printer::{ColorSpecs, StandardBuilder, JSON};
+use termcolor::ColorChoice;
+
+use fragment_core::query::ParsedQuery;
+use fragment_core::search::{self, SortMethod};
+use fragment_core::title::TitleStrategy;
+use fragment_core::Note;
+
+use crate::FragmentError;
+
+#[derive(FromArgs)]
+/// Search notes from the command line and print the results.
+#[argh(subcommand, name = "search")]
+pub struct SearchArgs {
+    /// print results as a JSON array
+    #[argh(switch)]
+    json: bool,
+
+    /// only print this many results
+    #[argh(option, short = 'n')]
+    limit: Option<usize>,
+
+    /// the query, in the same syntax as the search box
+    #[argh(positional)]
+    query: Vec<String>,
+}
+
+#[derive(FromArgs)]
+/// Print the lines matching a query in every note, like grep.
+#[argh(subcommand, name = "grep")]
+pub struct GrepArgs {
+    /// print results as grep's JSON Lines messages
+    #[argh(switch)]
+    json: bool,
+
+    /// when to color the output: auto, always or never
+    #[argh(option, default = "ColorWhen::Auto")]
+    color: ColorWhen,
+
+    /// lines to show after each match
+    #[argh(option, short = 'A')]
+    after_context: Option<usize>,
+
+    /// lines to show before each match
+    #[argh(option, short = 'B')]
+    before_context: Option<usize>,
+
+    /// lines to show before and after each match
+    #[argh(option, short = 'C')]
+    context: Option<usize>,
+
+    /// the query, in the same syntax as the search box. Filters pick the
+    /// notes, and the rest is the regex matched against their lines.
+    #[argh(positional)]
+    query: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorWhen {
+    /// Color when printing to a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorWhen {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorWhen::Auto),
+            "always" => Ok(ColorWhen::Always),
+            "never" => Ok(ColorWhen::Never),
+            _ => Err(format!(
+                "unknown color choice '{}', expected one of: auto, always, never",
+                s
+            )),
+        }
+    }
+}
+
+impl ColorWhen {
+    fn choice(self) -> ColorChoice {
+        match self {
+            ColorWhen::Auto if cli::is_tty_stdout() => ColorChoice::Auto,
+            ColorWhen::Auto | ColorWhen::Never => ColorChoice::Never,
+            ColorWhen::Always => ColorChoice::Always,
+        }
+    }
+}
+
+/// Runs a search without opening a window. Returns whether anything
+/// matched, so scripts can tell from the exit code like with grep.
+pub fn search(root: &str, titles: TitleStrategy, args: SearchArgs) -> Result<bool, FragmentError> {
+    let query = args.query.join(" ");
+    let results = search::search(&query, root, titles, &AtomicU64::new(0), 1)
+        .map_err(|err| FragmentError::Search(err.to_string()))?;
+
+    let mut matches = rank(&query, results.matches)?;
+    if let Some(limit) = args.limit {
+        matches.truncate(limit);
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if args.json {
+        print_json(&mut out, &matches)
+    } else {
+        print_text(&mut out, &matches)
+    }
+    .map_err(FragmentError::Io)?;
+
+    Ok(!matches.is_empty())
+}
+
+/// Greps the notes a query picks out, printing every matching line. Notes
+/// are walked and filtered exactly as for a search. Returns whether
+/// anything matched.
+pub fn grep(root: &str, titles: TitleStrategy, args: GrepArgs) -> Result<bool, FragmentError> {
+    let notes =
+        search::list_of_all_files(root, SortMethod::DateNewest, titles, &AtomicU64::new(0), 1);
+    let query = ParsedQuery::parse_for(&args.query.join(" "), &notes);
+    let matcher =
+        search::matcher(&query.pattern).map_err(|err| FragmentError::Search(err.to_string()))?;
+    let notes = notes.iter().filter(|note| query.matches(note));
+
+    let mut builder = search::searcher_builder();
+    builder
+        .line_number(true)
+        .before_context(args.before_context.or(args.context).unwrap_or(0))
+        .after_context(args.after_context.or(args.context).unwrap_or(0));
+    let mut searcher = builder.build();
+
+    let mut found = false;
+    if args.json {
+        let mut printer = JSON::new(io::stdout());
+        for note in notes {
+            let path = Path::new(note.path.as_ref());
+            let mut sink = printer.sink_with_path(&matcher, path);
+            if let Err(err) = searcher.search_path(&matcher, path, &mut sink) {
+                eprintln!("{}: {}", path.display(), err);
+            }
+            found |= sink.has_match();
+        }
+    } else {
+        let mut printer = StandardBuilder::new()
+            .color_specs(ColorSpecs::default_with_color())
+            .build(cli::stdout(args.color.choice()));
+        for note in notes {
+            let path = Path::new(note.path.as_ref());
+            let mut sink = printer.sink_with_path(&matcher, path);
+            if let Err(err) = searcher.search_path(&matcher, path, &mut sink) {
+                eprintln!("{}: {}", path.display(), err);
+            }
+            found |= sink.has_match();
+        }
+    }
+
+    Ok(found)
+}
+
+/// Puts notes whose title matches first, then the rest, each newest first as
+/// they come back from the search.
+fn rank(query: &str, mut matches: Vec<Note>) -> Result<Vec<Note>, FragmentError> {
+    let pattern = ParsedQuery::parse_for(query, &matches).pattern;
+    if pattern.is_empty() {
+        return Ok(matches);
+    }
+    let matcher =
+        search::matcher(&pattern).map_err(|err| FragmentError::Search(err.to_string()))?;
+    let title_matches = |note: &Note| matcher.is_match(note.title.as_bytes()).unwrap_or(false);
+
+    // Sorting is stable, so date order holds within each group
+    matches.sort_by_key(|note| !title_matches(note));
+    Ok(matches)
+}
+
+/// `path:line:col: title: matching line`, one note per line
+fn print_text(out: &mut impl Write, matches: &[Note]) -> io::Result<()> {
+    for note in matches {
+        match (note.found_at, &note.found_line) {
+            (Some((line, col)), Some(found)) => {
+                // The found line starts with its line number
+                let text = found.splitn(2, ": ").nth(1).unwrap_or(found);
+                writeln!(
+                    out,
+                    "{}:{}:{}: {}: {}",
+                    note.path, line, col, note.title, text
+                )?
+            }
+            _ => writeln!(out, "{}: {}", note.path, note.title)?,
+        }
+    }
+    Ok(())
+}
+
+fn print_json(out: &mut impl Write, matches: &[Note]) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, note) in matches.iter().enumerate() {
+        let tags: Vec<String> = note.tags.iter().map(|tag| json_string(tag)).collect();
+        let (line, col) = match note.found_at {
+            Some((line, col)) => (line.to_string(), col.to_string()),
+            None => ("null".to_string(), "null".to_string()),
+        };
+        writeln!(
+            out,
+            "  {{\"path\": {}, \"title\": {}, \"folder\": {}, \"modified\": {}, \
+             \"tags\": [{}], \"line\": {}, \"column\": {}, \"text\": {}}}{}",
+            json_string(&note.path),
+            json_string(&note.title),
+            json_string(&note.folder),
+            json_string(&rfc3339(note.modified)),
+            tags.join(", "),
+            line,
+            col,
+            note.found_line
+                .as_ref()
+                .map(|found| json_string(found.splitn(2, ": ").nth(1).unwrap_or(found)))
+                .unwrap_or_else(|| "null".to_string()),
+            if i + 1 < matches.len() { "," } else { "" }
+        )?;
+    }
+    writeln!(out, "]")
+}
+
+fn rfc3339(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).to_rfc3339()
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    fn note(title: &str, found: Option<(u64, u64, &str)>) -> Note {
+        Note {
+            path: format!("/notes/{}.md", title).into(),
+            file_name: format!("{}.md", title).into(),
+            modified: UNIX_EPOCH,
+            created: None,
+            folder: "".into(),
+            size: 0,
+            word_count: 0,
+            tags: Arc::new(vec!["work".into()]),
+            aliases: Arc::new(Vec::new()),
+            links: Arc::new(Vec::new()),
+            tasks: Arc::new(Vec::new()),
+            front_matter: Arc::new(Default::default()),
+            title: title.into(),
+            found_line: found.map(|(line, _, text)| format!("{}: {}", line, text).into()),
+            found_at: found.map(|(line, col, _)| (line, col)),
+        }
+    }
+
+    fn printed(print: fn(&mut Vec<u8>, &[Note]) -> io::Result<()>, notes: &[Note]) -> String {
+        let mut out = Vec::new();
+        print(&mut out, notes).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn search_args_take_options_then_the_query() {
+        let args = SearchArgs::from_args(&["search"], &["--json", "-n", "3", "tag:work", "agenda"])
+            .unwrap();
+        assert!(args.json);
+        assert_eq!(args.limit, Some(3));
+        assert_eq!(args.query, vec!["tag:work", "agenda"]);
+
+        assert!(SearchArgs::from_args(&["search"], &["-n", "many"]).is_err());
+    }
+
+    #[test]
+    fn text_output_has_the_match_position_when_there_is_one() {
+        let notes = vec![
+            note("Agenda", Some((3, 5, "the agenda: items"))),
+            note("Plans", None),
+        ];
+        assert_eq!(
+            printed(|out, notes| print_text(out, notes), &notes),
+            "/notes/Agenda.md:3:5: Agenda: the agenda: items\n/notes/Plans.md: Plans\n"
+        );
+    }
+
+    #[test]
+    fn json_output_escapes_strings_and_uses_null_for_no_match() {
+        let notes = vec![note("Say \"hi\"\t", None)];
+        let json = printed(|out, notes| print_json(out, notes), &notes);
+        assert!(json.starts_with("[\n  {\"path\": \"/notes/Say \\\"hi\\\"\\t.md\""));
+        assert!(json.contains("\"tags\": [\"work\"]"));
+        assert!(json.contains("\"line\": null, \"column\": null, \"text\": null}\n]"));
+        assert_eq!(json_string("a\\b\u{1}\n"), "\"a\\\\b\\u0001\\n\"");
+    }
+
+    #[test]
+    fn title_matches_go_first_keeping_their_order() {
+        let matches = vec![
+            note("Daily log", None),
+            note("Agenda for Monday", None),
+            note("Notes", None),
+            note("Agenda archive", None),
+        ];
+        let ranked = rank("tag:work Agenda", matches).unwrap();
+        let titles: Vec<&str> = ranked.iter().map(|note| note.title.as_ref()).collect();
+        assert_eq!(
+            titles,
+            vec!["Agenda for Monday", "Agenda archive", "Daily log", "Notes"]
+        );
+    }
+
+    #[test]
+    fn grep_args_take_context_and_color() {
+        let args = GrepArgs::from_args(
+            &["grep"],
+            &["-C", "2", "-A", "4", "--color", "never", "tag:work", "TODO"],
+        )
+        .unwrap();
+        assert!(!args.json);
+        assert_eq!(args.context, Some(2));
+        assert_eq!(args.after_context, Some(4));
+        assert_eq!(args.before_context, None);
+        assert_eq!(args.color, ColorWhen::Never);
+        assert_eq!(args.query, vec!["tag:work", "TODO"]);
+
+        let args = GrepArgs::from_args(&["grep"], &["needle"]).unwrap();
+        assert_eq!(args.color, ColorWhen::Auto);
+        assert!(GrepArgs::from_args(&["grep"], &["--color", "sometimes", "needle"]).is_err());
+    }
+
+    #[test]
+    fn color_choices_are_auto_always_or_never() {
+        assert_eq!("always".parse(), Ok(ColorWhen::Always));
+        assert_eq!("never".parse(), Ok(ColorWhen::Never));
+        assert_eq!("auto".parse(), Ok(ColorWhen::Auto));
+        assert!("Always".parse::<ColorWhen>().is_err());
+        assert_eq!(ColorWhen::Never.choice(), ColorChoice::Never);
+        assert_eq!(ColorWhen::Always.choice(), ColorChoice::Always);
+    }
+}
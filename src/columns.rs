@@ -0,0 +1,385 @@
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use chrono::prelude::*;
+
+use druid::widget::prelude::*;
+use druid::{
+    theme, Command, ContextMenu, LocalizedString, MenuDesc, MenuItem, Point, Rect, Target,
+    WidgetPod,
+};
+
+use fragment_core::search::SortMethod;
+
+use crate::delegate::{SET_SORT, TOGGLE_COLUMN, TOGGLE_RELATIVE_DATES};
+use crate::search::ListItem;
+use crate::FragmentState;
+
+const MIN_COLUMN_WIDTH: f64 = 40.0;
+
+#[derive(Clone, Copy, Data, Debug, PartialEq)]
+pub enum ColumnKind {
+    Title,
+    Modified,
+    Created,
+    Folder,
+    Size,
+    Words,
+    Tags,
+    Properties,
+}
+
+impl ColumnKind {
+    pub const ALL: [ColumnKind; 8] = [
+        ColumnKind::Title,
+        ColumnKind::Modified,
+        ColumnKind::Created,
+        ColumnKind::Folder,
+        ColumnKind::Size,
+        ColumnKind::Words,
+        ColumnKind::Tags,
+        ColumnKind::Properties,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColumnKind::Title => "Title",
+            ColumnKind::Modified => "Date Modified",
+            ColumnKind::Created => "Date Created",
+            ColumnKind::Folder => "Folder",
+            ColumnKind::Size => "Size",
+            ColumnKind::Words => "Words",
+            ColumnKind::Tags => "Tags",
+            ColumnKind::Properties => "Properties",
+        }
+    }
+}
+
+#[derive(Clone, Data, Debug)]
+pub struct Column {
+    pub kind: ColumnKind,
+    pub visible: bool,
+    pub width: f64,
+}
+
+#[derive(Clone, Data)]
+pub struct Columns {
+    pub columns: Arc<Vec<Column>>,
+    pub relative_dates: bool,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        let column = |kind, visible, width| Column {
+            kind,
+            visible,
+            width,
+        };
+        Columns {
+            columns: Arc::new(vec![
+                column(ColumnKind::Title, true, 300.0),
+                column(ColumnKind::Modified, true, 150.0),
+                column(ColumnKind::Created, false, 150.0),
+                column(ColumnKind::Folder, false, 150.0),
+                column(ColumnKind::Size, false, 80.0),
+                column(ColumnKind::Words, false, 80.0),
+                column(ColumnKind::Tags, false, 150.0),
+                column(ColumnKind::Properties, false, 200.0),
+            ]),
+            relative_dates: false,
+        }
+    }
+}
+
+impl Columns {
+    pub fn get(&self, kind: ColumnKind) -> &Column {
+        self.columns
+            .iter()
+            .find(|c| c.kind == kind)
+            .expect("Every column kind has a column")
+    }
+
+    pub fn toggle(&mut self, kind: ColumnKind) {
+        // The title column always stays visible so rows never end up empty
+        if kind == ColumnKind::Title {
+            return;
+        }
+        for column in Arc::make_mut(&mut self.columns).iter_mut() {
+            if column.kind == kind {
+                column.visible = !column.visible;
+            }
+        }
+    }
+
+    pub fn resize(&mut self, kind: ColumnKind, delta: f64) {
+        for column in Arc::make_mut(&mut self.columns).iter_mut() {
+            if column.kind == kind {
+                column.width = (column.width + delta).max(MIN_COLUMN_WIDTH);
+            }
+        }
+    }
+
+    /// The text shown for `item` in the given column
+    pub fn cell_text(&self, kind: ColumnKind, item: &ListItem) -> String {
+        match kind {
+            ColumnKind::Title if item.pinned => format!("📌 {}", item.title),
+            ColumnKind::Title => item.title.to_string(),
+            ColumnKind::Modified => format_date(item.modified, self.relative_dates),
+            ColumnKind::Created => item
+                .created
+                .map(|created| format_date(created, self.relative_dates))
+                .unwrap_or_default(),
+            ColumnKind::Folder => item.folder.to_string(),
+            ColumnKind::Size => format_size(item.size),
+            ColumnKind::Words => item.word_count.to_string(),
+            ColumnKind::Tags => item
+                .tags
+                .iter()
+                .map(|tag| format!("#{}", tag))
+                .collect::<Vec<_>>()
+                .join(" "),
+            // Front matter fields that don't already have a column of their own
+            ColumnKind::Properties => item
+                .front_matter
+                .fields
+                .iter()
+                .filter(|(key, _)| !["title", "tags", "aliases", "created"].contains(&key.as_str()))
+                .map(|(key, values)| format!("{}: {}", key, values.join(", ")))
+                .collect::<Vec<_>>()
+                .join("  "),
+        }
+    }
+
+    pub fn context_menu(&self, sort: SortMethod) -> MenuDesc<FragmentState> {
+        let mut menu = MenuDesc::empty();
+        for column in self.columns.iter().filter(|c| c.kind != ColumnKind::Title) {
+            menu = menu.append(
+                MenuItem::new(
+                    LocalizedString::new("fragment-toggle-column")
+                        .with_placeholder(column.kind.label()),
+                    Command::new(TOGGLE_COLUMN, column.kind, Target::Global),
+                )
+                .selected(column.visible),
+            );
+        }
+        menu = menu.append_separator().append(
+            MenuItem::new(
+                LocalizedString::new("fragment-relative-dates").with_placeholder("Relative Dates"),
+                Command::new(TOGGLE_RELATIVE_DATES, (), Target::Global),
+            )
+            .selected(self.relative_dates),
+        );
+        menu = menu.append_separator();
+        for method in SortMethod::ALL.iter().copied() {
+            menu = menu.append(
+                MenuItem::new(
+                    LocalizedString::new("fragment-sort").with_placeholder(sort_label(method)),
+                    Command::new(SET_SORT, method, Target::Global),
+                )
+                .selected(method == sort),
+            );
+        }
+        menu
+    }
+}
+
+fn sort_label(method: SortMethod) -> &'static str {
+    match method {
+        SortMethod::DateNewest => "Newest First",
+        SortMethod::DateOldest => "Oldest First",
+        SortMethod::TitleAZ => "Title A–Z",
+        SortMethod::TitleZA => "Title Z–A",
+    }
+}
+
+pub fn format_date(time: SystemTime, relative: bool) -> String {
+    if relative {
+        return relative_date(time);
+    }
+    let dt: DateTime<Local> = time.into();
+    dt.format("%b %e, %Y").to_string()
+}
+
+fn relative_date(time: SystemTime) -> String {
+    let seconds_old = SystemTime::now()
+        .duration_since(time)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let plural = |n: u64, unit: &str| {
+        if n == 1 {
+            format!("1 {} ago", unit)
+        } else {
+            format!("{} {}s ago", n, unit)
+        }
+    };
+
+    match seconds_old {
+        0..=59 => "just now".to_string(),
+        60..=3_599 => plural(seconds_old / 60, "minute"),
+        3_600..=86_399 => plural(seconds_old / 3_600, "hour"),
+        86_400..=2_591_999 => plural(seconds_old / 86_400, "day"),
+        2_592_000..=31_535_999 => plural(seconds_old / 2_592_000, "month"),
+        _ => plural(seconds_old / 31_536_000, "year"),
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1_023 => format!("{} B", bytes),
+        1_024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1_024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// Anything a column cell can read the current column layout from
+pub trait HasColumns {
+    fn columns(&self) -> &Columns;
+}
+
+impl HasColumns for Columns {
+    fn columns(&self) -> &Columns {
+        self
+    }
+}
+
+impl HasColumns for (Columns, ListItem) {
+    fn columns(&self) -> &Columns {
+        &self.0
+    }
+}
+
+/// Lays its child out at the width of its column, or hides it entirely when
+/// the column is toggled off.
+pub struct ColumnCell<T, W> {
+    kind: ColumnKind,
+    inner: WidgetPod<T, W>,
+}
+
+impl<T: Data + HasColumns, W: Widget<T>> ColumnCell<T, W> {
+    pub fn new(kind: ColumnKind, inner: W) -> Self {
+        ColumnCell {
+            kind,
+            inner: WidgetPod::new(inner),
+        }
+    }
+}
+
+impl<T: Data + HasColumns, W: Widget<T>> Widget<T> for ColumnCell<T, W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if data.columns().get(self.kind).visible {
+            self.inner.event(ctx, event, data, env);
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.inner.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        if !old_data.columns().same(data.columns()) {
+            ctx.request_layout();
+        }
+        self.inner.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let column = data.columns().get(self.kind);
+        let width = if column.visible { column.width } else { 0.0 };
+        let child_bc = BoxConstraints::new(
+            Size::new(width, bc.min().height),
+            Size::new(width, bc.max().height),
+        );
+        let size = self.inner.layout(ctx, &child_bc, data, env);
+        self.inner
+            .set_layout_rect(ctx, data, env, Rect::from_origin_size(Point::ORIGIN, size));
+        bc.constrain(Size::new(width, size.height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        if data.columns().get(self.kind).visible {
+            self.inner.paint(ctx, data, env);
+        }
+    }
+}
+
+/// A thin drag handle in the header that resizes the column to its left.
+pub struct ResizeHandle {
+    kind: ColumnKind,
+    last_x: f64,
+}
+
+impl ResizeHandle {
+    pub fn new(kind: ColumnKind) -> Self {
+        ResizeHandle { kind, last_x: 0.0 }
+    }
+}
+
+impl Widget<Columns> for ResizeHandle {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Columns, _env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                self.last_x = mouse.window_pos.x;
+                ctx.set_active(true);
+                ctx.set_handled();
+            }
+            Event::MouseMove(mouse) => {
+                ctx.set_cursor(&druid::Cursor::ResizeLeftRight);
+                if ctx.is_active() {
+                    data.resize(self.kind, mouse.window_pos.x - self.last_x);
+                    self.last_x = mouse.window_pos.x;
+                }
+            }
+            Event::MouseUp(_) if ctx.is_active() => {
+                ctx.set_active(false);
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _: &Columns, _: &Env) {
+        if let LifeCycle::HotChanged(_) = event {
+            ctx.request_paint();
+        }
+    }
+
+    fn update(&mut self, _: &mut UpdateCtx, _: &Columns, _: &Columns, _: &Env) {}
+
+    fn layout(&mut self, _: &mut LayoutCtx, bc: &BoxConstraints, _: &Columns, _: &Env) -> Size {
+        bc.constrain(Size::new(4.0, bc.max().height.min(24.0)))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _: &Columns, env: &Env) {
+        let bounds = ctx.size().to_rect();
+        let color = if ctx.is_hot() || ctx.is_active() {
+            env.get(theme::PRIMARY_LIGHT)
+        } else {
+            env.get(theme::BACKGROUND_LIGHT)
+        };
+        ctx.fill(bounds, &color);
+    }
+}
+
+/// Shows the column toggle menu when the header is right-clicked.
+pub struct HeaderMenu;
+
+impl<W: Widget<FragmentState>> druid::widget::Controller<FragmentState, W> for HeaderMenu {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut FragmentState,
+        env: &Env,
+    ) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_right() => {
+                ctx.show_context_menu(ContextMenu::new(
+                    data.columns.context_menu(data.sort),
+                    mouse.window_pos,
+                ));
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
@@ -1,15 +1,29 @@
-use chrono::prelude::*;
+use std::sync::Arc;
 
-use druid::widget::{Either, Flex, Label, LineBreaking, List, Painter, Scroll, TextBox, WidgetExt};
-use druid::{theme, Color, Env, RenderContext, Widget};
+use druid::widget::{
+    Button, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, Painter, RawLabel, Scroll,
+    SizedBox, TextBox, ViewSwitcher, WidgetExt,
+};
+use druid::{theme, Color, Command, Env, Lens, RenderContext, Target, Widget};
 
+use super::actions::{Prompt, RowMenu};
+use super::columns::{ColumnCell, ColumnKind, Columns, HeaderMenu, ResizeHandle};
+use super::delegate::{CANCEL_PROMPT, CONFIRM_PROMPT, OPEN_LINK};
+use super::history::{self, RecordQuery};
 use super::keyup::KeyUp;
+use super::shortcuts::PromptFocus;
+use super::todos::{self, ResultsView};
+use super::virtual_list::VirtualList;
+use super::watch::LiveUpdates;
 use super::ListItem;
 
 use super::FragmentState;
 
-fn list_item() -> impl Widget<ListItem> {
-    let painter: Painter<ListItem> = Painter::new(|ctx, _, env| {
+/// Height of a result row: the cells over the line that matched
+const ROW_HEIGHT: f64 = 56.0;
+
+fn list_item() -> impl Widget<(Columns, ListItem)> {
+    let painter: Painter<(Columns, ListItem)> = Painter::new(|ctx, _, env| {
         let bounds = ctx.size().to_rect();
 
         if ctx.is_hot() {
@@ -31,39 +45,24 @@ fn list_item() -> impl Widget<ListItem> {
         ctx.stroke(path, &stroke_color, 1.0);
     });
 
+    let mut cells = Flex::row();
+    for kind in ColumnKind::ALL.iter().copied() {
+        cells.add_child(ColumnCell::new(
+            kind,
+            Label::new(move |data: &(Columns, ListItem), _: &Env| data.0.cell_text(kind, &data.1))
+                .padding(5.0)
+                .expand_width(),
+        ));
+    }
+
     Flex::column()
-        .with_child(
-            Flex::row()
-                .with_flex_child(
-                    Label::new(|data: &ListItem, _: &Env| (*data).file_name.to_string())
-                        .padding(5.0)
-                        .expand_width(),
-                    2.0,
-                )
-                .with_flex_child(
-                    Label::new(|data: &ListItem, _: &Env| {
-                        let today = Utc::now().timestamp();
-                        let seconds_old = data
-                            .modified
-                            .elapsed()
-                            .expect("Couldn't get elapsed.")
-                            .as_secs() as i64;
-
-                        let date_modified = today - seconds_old;
-                        let dt: DateTime<Utc> = Utc.timestamp(date_modified, 0);
-
-                        dt.format("%b %e, %Y").to_string()
-                    })
-                    .padding(5.0)
-                    .expand_width(),
-                    1.0,
-                ),
-        )
+        .with_child(cells)
         .with_child(
             Label::new(
-                |data: &ListItem, _: &Env| match (*data).found_line.clone() {
+                |data: &(Columns, ListItem), _: &Env| match data.1.found_line.clone() {
                     Some(line) => line.to_string(),
-                    None => "Couldn't read file".to_string(),
+                    // Matched on the title alone
+                    None => String::new(),
                 },
             )
             .with_text_color(Color::rgb8(200, 200, 200))
@@ -71,33 +70,96 @@ fn list_item() -> impl Widget<ListItem> {
             .expand_width(),
         )
         .background(painter)
-        .on_click(ListItem::preview_note)
+        .on_click(|ctx, data: &mut (Columns, ListItem), env| {
+            ListItem::preview_note(ctx, &mut data.1, env)
+        })
+        .controller(RowMenu)
 }
 
-pub(crate) fn top_pane() -> impl Widget<FragmentState> {
-    Flex::column()
-        .with_child(
+fn header() -> impl Widget<Columns> {
+    let mut header = Flex::row();
+    for kind in ColumnKind::ALL.iter().copied() {
+        header.add_child(ColumnCell::new(
+            kind,
             Flex::row()
                 .with_flex_child(
-                    Label::new("Title")
+                    Label::new(kind.label())
                         .padding(5.0)
-                        .background(Color::BLACK)
-                        .expand_width(),
-                    2.0,
-                )
-                .with_flex_child(
-                    Label::new("Date Modified")
-                        .padding(5.0)
-                        .background(theme::PRIMARY_DARK)
-                        .expand_width(),
+                        .expand_width()
+                        .background(theme::PRIMARY_DARK),
                     1.0,
                 )
-                .must_fill_main_axis(true),
-        )
+                .with_child(ResizeHandle::new(kind)),
+        ));
+    }
+    header.background(Color::BLACK)
+}
+
+/// Hands each row the column layout alongside its own `ListItem`, from the
+/// results or the recent notes
+struct RowsLens {
+    recent: bool,
+}
+
+impl RowsLens {
+    fn rows<'a>(&self, data: &'a mut FragmentState) -> &'a mut Arc<Vec<ListItem>> {
+        if self.recent {
+            &mut data.recent_notes
+        } else {
+            &mut data.results
+        }
+    }
+}
+
+impl Lens<FragmentState, (Columns, Arc<Vec<ListItem>>)> for RowsLens {
+    fn with<V, F: FnOnce(&(Columns, Arc<Vec<ListItem>>)) -> V>(
+        &self,
+        data: &FragmentState,
+        f: F,
+    ) -> V {
+        let rows = if self.recent {
+            &data.recent_notes
+        } else {
+            &data.results
+        };
+        f(&(data.columns.clone(), rows.clone()))
+    }
+
+    fn with_mut<V, F: FnOnce(&mut (Columns, Arc<Vec<ListItem>>)) -> V>(
+        &self,
+        data: &mut FragmentState,
+        f: F,
+    ) -> V {
+        let mut rows = (data.columns.clone(), self.rows(data).clone());
+        let value = f(&mut rows);
+        data.columns = rows.0;
+        *self.rows(data) = rows.1;
+        value
+    }
+}
+
+/// The column header over a list of notes. Only the rows in view are
+/// built, keyed by path so they survive the results being replaced.
+fn notes_list(recent: bool) -> impl Widget<FragmentState> {
+    let rows: VirtualList<Columns, ListItem, _, _> =
+        VirtualList::new(list_item, |item: &ListItem| item.path.clone(), ROW_HEIGHT);
+    Flex::column()
+        .with_child(header().lens(FragmentState::columns).controller(HeaderMenu))
+        .with_flex_child(rows.lens(RowsLens { recent }), 1.0)
+}
+
+pub(crate) fn top_pane() -> impl Widget<FragmentState> {
+    Flex::column()
+        .with_child(todos::view_switcher())
         .with_flex_child(
-            Scroll::new(List::new(|| list_item()).lens(FragmentState::results))
-                .vertical()
-                .expand_width(),
+            ViewSwitcher::new(
+                |data: &FragmentState, _: &Env| data.view,
+                |view: &ResultsView, _, _| match view {
+                    ResultsView::Notes => Box::new(notes_list(false)),
+                    ResultsView::Todos => Box::new(todos::todos_list()),
+                    ResultsView::Recent => Box::new(notes_list(true)),
+                },
+            ),
             1.0,
         )
         .border(Color::rgb8(100, 100, 100), 1.0)
@@ -106,27 +168,205 @@ pub(crate) fn top_pane() -> impl Widget<FragmentState> {
 }
 
 pub(crate) fn search_box() -> impl Widget<FragmentState> {
-    TextBox::new()
-        .lens(FragmentState::query)
-        .controller(KeyUp::new())
-        .expand_width()
+    Flex::row()
+        .with_flex_child(
+            TextBox::new()
+                .lens(FragmentState::query)
+                .controller(KeyUp::new())
+                .controller(RecordQuery::new())
+                .expand_width(),
+            1.0,
+        )
+        .with_spacer(5.0)
+        .with_child(history::history_button())
         .padding(5.0)
 }
 
+fn front_matter_toggle() -> impl Widget<FragmentState> {
+    Either::new(
+        |data: &FragmentState, _: &Env| match (&data.selected_note, data.selected_body()) {
+            (Some(note), Some(body)) => body.len() != note.len(),
+            _ => false,
+        },
+        Label::dynamic(|data: &FragmentState, _: &Env| {
+            if data.show_front_matter {
+                "▾ Front matter".to_string()
+            } else {
+                "▸ Front matter".to_string()
+            }
+        })
+        .with_text_color(Color::rgb8(200, 200, 200))
+        .padding(druid::Insets::new(0.0, 0.0, 0.0, 5.0))
+        .on_click(|_, data: &mut FragmentState, env| {
+            data.show_front_matter = !data.show_front_matter;
+            data.render_preview(env);
+        }),
+        SizedBox::empty(),
+    )
+}
+
+/// Switches the preview between rendered markdown and the note as written
+fn source_toggle() -> impl Widget<FragmentState> {
+    Either::new(
+        |data: &FragmentState, _: &Env| data.selected_note.is_some(),
+        Label::dynamic(|data: &FragmentState, _: &Env| {
+            if data.show_source {
+                "Show rendered".to_string()
+            } else {
+                "Show source".to_string()
+            }
+        })
+        .with_text_color(Color::rgb8(200, 200, 200))
+        .padding(druid::Insets::new(0.0, 0.0, 0.0, 5.0))
+        .on_click(|_, data: &mut FragmentState, _| data.show_source = !data.show_source),
+        SizedBox::empty(),
+    )
+}
+
+pub(crate) fn prompt_bar() -> impl Widget<FragmentState> {
+    let hint = Label::dynamic(|data: &FragmentState, _: &Env| match data.prompt {
+        Prompt::Rename if data.rename_referrers.is_empty() => {
+            "No other notes link here".to_string()
+        }
+        Prompt::Rename => {
+            let titles: Vec<&str> = data
+                .rename_referrers
+                .iter()
+                .map(|note| note.title.as_ref())
+                .collect();
+            format!("Links will be updated in: {}", titles.join(", "))
+        }
+        Prompt::Move => "Folder under the notes root, created if it doesn't exist".to_string(),
+        Prompt::SaveSearch => format!("Name for \"{}\"", data.query.trim()),
+        Prompt::None => String::new(),
+    })
+    .with_text_color(Color::rgb8(200, 200, 200))
+    .with_line_break_mode(LineBreaking::WordWrap);
+
+    Either::new(
+        |data: &FragmentState, _: &Env| data.prompt != Prompt::None,
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(
+                Flex::row()
+                    .with_child(Label::dynamic(|data: &FragmentState, _: &Env| {
+                        match data.prompt {
+                            Prompt::Move => "Move to",
+                            Prompt::SaveSearch => "Save search as",
+                            _ => "Rename to",
+                        }
+                        .to_string()
+                    }))
+                    .with_spacer(5.0)
+                    .with_flex_child(
+                        TextBox::new()
+                            .lens(FragmentState::prompt_text)
+                            .controller(PromptFocus)
+                            .expand_width(),
+                        1.0,
+                    )
+                    .with_spacer(5.0)
+                    .with_child(
+                        Button::new("OK").on_click(|ctx, _, _| ctx.submit_command(CONFIRM_PROMPT)),
+                    )
+                    .with_child(
+                        Button::new("Cancel")
+                            .on_click(|ctx, _, _| ctx.submit_command(CANCEL_PROMPT)),
+                    ),
+            )
+            .with_child(hint)
+            .padding(5.0),
+        SizedBox::empty(),
+    )
+}
+
+pub(crate) fn status_bar() -> impl Widget<FragmentState> {
+    Flex::row()
+        .with_flex_child(
+            Label::dynamic(|data: &FragmentState, _: &Env| data.status.clone())
+                .with_text_color(Color::rgb8(200, 200, 200))
+                .expand_width(),
+            1.0,
+        )
+        .with_child(live_updates_indicator())
+        .padding(druid::Insets::new(5.0, 0.0, 5.0, 5.0))
+        .expand_width()
+}
+
+/// Whether changes on disk are showing up by themselves, and if not, why
+fn live_updates_indicator() -> impl Widget<FragmentState> {
+    Label::dynamic(|data: &FragmentState, _: &Env| match &data.live_updates {
+        LiveUpdates::Starting => "○ Starting live updates".to_string(),
+        LiveUpdates::Watching => "● Live".to_string(),
+        LiveUpdates::Polling(None) => "● Live (polling)".to_string(),
+        LiveUpdates::Polling(Some(reason)) => format!("● Live (polling, {})", reason),
+        LiveUpdates::Down(reason) => format!("○ Not live: {}", reason),
+    })
+    .env_scope(|env, data: &FragmentState| {
+        let color = match data.live_updates {
+            LiveUpdates::Watching | LiveUpdates::Polling(None) => Color::rgb8(120, 190, 120),
+            LiveUpdates::Polling(Some(_)) => Color::rgb8(210, 180, 90),
+            LiveUpdates::Starting | LiveUpdates::Down(_) => Color::rgb8(220, 110, 100),
+        };
+        env.set(theme::LABEL_COLOR, color);
+    })
+}
+
+pub(crate) fn tag_completions() -> impl Widget<FragmentState> {
+    Either::new(
+        |data: &FragmentState, _: &Env| !data.tag_completions().is_empty(),
+        Label::dynamic(|data: &FragmentState, _: &Env| {
+            let tags: Vec<String> = data
+                .tag_completions()
+                .iter()
+                .map(|tag| format!("#{}", tag))
+                .collect();
+            format!("Tab to complete: {}", tags.join("  "))
+        })
+        .with_text_color(Color::rgb8(200, 200, 200))
+        .padding(druid::Insets::new(10.0, 0.0, 5.0, 5.0))
+        .expand_width(),
+        SizedBox::empty(),
+    )
+}
+
 pub(crate) fn text_pane() -> impl Widget<FragmentState> {
+    let source = Label::dynamic(
+        |data: &FragmentState, _env: &Env| match &data.selected_note {
+            // Front matter stays collapsed unless asked for
+            Some(_) if !data.show_front_matter => {
+                data.selected_body().unwrap_or_default().to_string()
+            }
+            Some(note) => note.clone(),
+            None => "No note selected".to_string(),
+        },
+    )
+    .with_line_break_mode(LineBreaking::WordWrap);
+    let rendered = RawLabel::new()
+        .with_line_break_mode(LineBreaking::WordWrap)
+        .lens(FragmentState::rendered_note);
     let text = Either::new(
-        |data: &FragmentState, env: &Env| data.selected_note.is_some(),
-        Label::dynamic(|data: &FragmentState, env: &Env| {
-            data.selected_note.clone().unwrap_or("wtf".to_string())
-        })
-        .with_line_break_mode(LineBreaking::WordWrap),
+        |data: &FragmentState, _env: &Env| data.selected_note.is_some(),
+        Either::new(
+            |data: &FragmentState, _: &Env| data.show_source,
+            source,
+            rendered,
+        ),
         Label::new(
             "Some day we'll have multiline text and it's going to be so great just you wait",
         )
         .with_line_break_mode(LineBreaking::WordWrap),
     );
-    Scroll::new(
+    let preview = Scroll::new(
         Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(
+                Flex::row()
+                    .with_child(front_matter_toggle())
+                    .with_flex_spacer(1.0)
+                    .with_child(source_toggle())
+                    .expand_width(),
+            )
             .with_child(text)
             // .with_child(Label::new("we'll have"))
             // .with_child(Label::new("multiline text"))
@@ -134,5 +374,49 @@ pub(crate) fn text_pane() -> impl Widget<FragmentState> {
             // .with_child(Label::new("you just wait")),
     )
     .vertical()
-    .expand_width()
+    .expand_width();
+
+    Flex::column()
+        .with_flex_child(preview, 1.0)
+        .with_child(links_pane())
+}
+
+fn link_row() -> impl Widget<Arc<str>> {
+    Label::new(|data: &Arc<str>, _: &Env| format!("[[{}]]", data))
+        .with_text_color(theme::PRIMARY_LIGHT)
+        .padding(druid::Insets::new(5.0, 2.0, 5.0, 2.0))
+        .on_click(|ctx, data: &mut Arc<str>, _| {
+            ctx.submit_command(Command::new(OPEN_LINK, data.clone(), Target::Global))
+        })
+}
+
+fn backlink_row() -> impl Widget<ListItem> {
+    Label::new(|data: &ListItem, _: &Env| data.title.to_string())
+        .with_text_color(theme::PRIMARY_LIGHT)
+        .padding(druid::Insets::new(5.0, 2.0, 5.0, 2.0))
+        .on_click(ListItem::preview_note)
+}
+
+/// Links out of the selected note and the notes linking back to it
+fn links_pane() -> impl Widget<FragmentState> {
+    let section = |title: &str| {
+        Label::new(title.to_string())
+            .with_text_color(Color::rgb8(200, 200, 200))
+            .padding(druid::Insets::new(5.0, 5.0, 5.0, 0.0))
+    };
+
+    Either::new(
+        |data: &FragmentState, _: &Env| !data.links.is_empty() || !data.backlinks.is_empty(),
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(section("Links"))
+            .with_child(List::new(|| link_row()).lens(FragmentState::links))
+            .with_child(section("Backlinks"))
+            .with_child(List::new(|| backlink_row()).lens(FragmentState::backlinks))
+            .expand_width()
+            .border(Color::rgb8(100, 100, 100), 1.0)
+            .rounded(5.0)
+            .padding(5.0),
+        SizedBox::empty(),
+    )
 }
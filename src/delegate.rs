@@ -1,51 +1,226 @@
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
-use druid::{AppDelegate, Command, DelegateCtx, Env, ExtEventSink, Handled, Selector, Target};
+use druid::{
+    AppDelegate, Command, DelegateCtx, Env, ExtEventSink, Handled, Selector, Target, WindowId,
+};
+use fragment_core::editor;
+use fragment_core::index::Index;
+use fragment_core::search::SortMethod;
+use fragment_core::watch::Batch;
 
-use super::{FragmentState, ListItem, Query};
+use super::actions::{NoteAction, Prompt};
+use super::columns::ColumnKind;
+use super::saved;
+use super::search::SearchResults;
+use super::todos::TodoItem;
+use super::watch::LiveUpdates;
+use super::{FragmentState, Query};
 
 pub const START_SEARCH: Selector = Selector::new("fragment.start-search");
-pub const FINISH_SEARCH: Selector<Vec<ListItem>> = Selector::new("fragment.finish-search");
-pub const START_REFRESH: Selector = Selector::new("fragment.refresh-search");
-pub const LOAD_NOTE: Selector<String> = Selector::new("fragment.load-note");
+pub const FINISH_SEARCH: Selector<SearchResults> = Selector::new("fragment.finish-search");
+/// Bring the results up to date with files changed on disk
+pub const APPLY_CHANGES: Selector<Batch> = Selector::new("fragment.apply-changes");
+/// The file watcher started, broke or fell back to polling
+pub const LIVE_UPDATES: Selector<LiveUpdates> = Selector::new("fragment.live-updates");
+/// Preview a note, given its path and contents
+pub const LOAD_NOTE: Selector<(Arc<str>, String)> = Selector::new("fragment.load-note");
+/// Follow a `[[wiki-link]]`, creating the target note if it doesn't exist
+pub const OPEN_LINK: Selector<Arc<str>> = Selector::new("fragment.open-link");
+/// Open a `[text](url)` link from the preview
+pub const OPEN_URL: Selector<Arc<str>> = Selector::new("fragment.open-url");
+pub const TOGGLE_COLUMN: Selector<ColumnKind> = Selector::new("fragment.toggle-column");
+pub const TOGGLE_RELATIVE_DATES: Selector = Selector::new("fragment.toggle-relative-dates");
+pub const FILTER_TAG: Selector<Arc<str>> = Selector::new("fragment.filter-tag");
+/// Limit the search to a folder under the root, or the whole root if empty
+pub const FILTER_FOLDER: Selector<Arc<str>> = Selector::new("fragment.filter-folder");
+/// Replace the query with a saved search's and run it
+pub const RUN_SAVED_SEARCH: Selector<Arc<str>> = Selector::new("fragment.run-saved-search");
+/// Replace the query with one from the history and run it
+pub const RECALL_QUERY: Selector<Arc<str>> = Selector::new("fragment.recall-query");
+/// Forget the saved search with the given name
+pub const DELETE_SAVED_SEARCH: Selector<Arc<str>> = Selector::new("fragment.delete-saved-search");
+/// Fresh match counts for the saved searches, by name
+pub const SAVED_COUNTS: Selector<Vec<(Arc<str>, Option<usize>)>> =
+    Selector::new("fragment.saved-counts");
+/// Expand or collapse a folder in the folder tree
+pub const TOGGLE_FOLDER: Selector<Arc<str>> = Selector::new("fragment.toggle-folder");
+/// Change the order the results are listed in
+pub const SET_SORT: Selector<SortMethod> = Selector::new("fragment.set-sort");
+/// Act on the note at the given path
+pub const NOTE_ACTION: Selector<(NoteAction, Arc<str>)> = Selector::new("fragment.note-action");
+pub const FOCUS_PROMPT: Selector = Selector::new("fragment.focus-prompt");
+pub const CONFIRM_PROMPT: Selector = Selector::new("fragment.confirm-prompt");
+pub const CANCEL_PROMPT: Selector = Selector::new("fragment.cancel-prompt");
+pub const UNDO: Selector = Selector::new("fragment.undo");
+/// Tick off an open task in its note
+pub const TOGGLE_TASK: Selector<TodoItem> = Selector::new("fragment.toggle-task");
+/// Open a note in the editor at the given line
+pub const OPEN_AT: Selector<(Arc<str>, u64)> = Selector::new("fragment.open-at");
 
 pub struct Delegate {
     pub event_sink: ExtEventSink,
+    pub index: Arc<Mutex<Index>>,
+}
+
+impl Delegate {
+    fn send_query(&self, data: &FragmentState) {
+        data.sender
+            .send(Query {
+                query: data.query.clone(),
+                scope: data.filters.scope(),
+                // Nothing else will notice notes coming and going
+                count_folders: !matches!(
+                    data.live_updates,
+                    LiveUpdates::Watching | LiveUpdates::Polling(_)
+                ),
+                event_sink: self.event_sink.clone(),
+            })
+            .unwrap();
+    }
+
+    fn count_saved_searches(&self, data: &FragmentState) {
+        // Over the whole folder, like the counts at startup, not just the
+        // notes the filters let through
+        saved::spawn_counts(
+            data.path.clone(),
+            data.titles,
+            self.index.clone(),
+            &data.saved_searches,
+            self.event_sink.clone(),
+        );
+    }
 }
 
 impl AppDelegate<FragmentState> for Delegate {
     fn command(
         &mut self,
-        _ctx: &mut DelegateCtx,
+        ctx: &mut DelegateCtx,
         _target: Target,
         cmd: &Command,
         data: &mut FragmentState,
-        _env: &Env,
+        env: &Env,
     ) -> Handled {
         if cmd.is(START_SEARCH) {
-            data.sender
-                .send(Query {
-                    query: data.query.clone(),
-                    event_sink: self.event_sink.clone(),
-                })
-                .unwrap();
+            self.send_query(data);
             Handled::Yes
         } else if let Some(search_result) = cmd.get(FINISH_SEARCH) {
-            data.results = Arc::new(search_result.clone());
+            data.set_results(search_result.clone());
             Handled::Yes
-        } else if cmd.is(START_REFRESH) {
-            data.sender
-                .send(Query {
-                    query: data.query.clone(),
-                    event_sink: self.event_sink.clone(),
-                })
-                .unwrap();
+        } else if let Some(batch) = cmd.get(APPLY_CHANGES) {
+            data.apply_changes(batch, env);
+            self.count_saved_searches(data);
             Handled::Yes
-        } else if let Some(note) = cmd.get(LOAD_NOTE) {
+        } else if let Some(live_updates) = cmd.get(LIVE_UPDATES) {
+            data.live_updates = live_updates.clone();
+            Handled::Yes
+        } else if let Some((path, note)) = cmd.get(LOAD_NOTE) {
             data.selected_note = Some(note.to_string());
+            data.selected_path = Some(path.clone());
+            data.record_recent(path);
+            data.update_links();
+            data.render_preview(env);
+            Handled::Yes
+        } else if let Some(target) = cmd.get(OPEN_LINK) {
+            if let Err(err) = data.open_link(target) {
+                data.status = format!("Couldn't open link to {}: {}", target, err);
+            }
+            Handled::Yes
+        } else if let Some(url) = cmd.get(OPEN_URL) {
+            if let Err(err) = data.open_url(url) {
+                data.status = format!("Couldn't open {}: {}", url, err);
+            }
+            Handled::Yes
+        } else if let Some(tag) = cmd.get(FILTER_TAG) {
+            data.add_tag_filter(tag);
+            self.send_query(data);
+            Handled::Yes
+        } else if let Some(folder) = cmd.get(FILTER_FOLDER) {
+            data.filter_folder(folder);
+            self.send_query(data);
+            Handled::Yes
+        } else if let Some(folder) = cmd.get(TOGGLE_FOLDER) {
+            data.toggle_folder(folder);
+            Handled::Yes
+        } else if let Some((action, path)) = cmd.get(NOTE_ACTION) {
+            match data.note_action(*action, path) {
+                Ok(()) => self.send_query(data),
+                Err(err) => data.status = format!("Couldn't {}: {}", action.description(), err),
+            }
+            if data.prompt != Prompt::None {
+                ctx.submit_command(FOCUS_PROMPT);
+            }
+            Handled::Yes
+        } else if cmd.is(CONFIRM_PROMPT) {
+            let saving = data.prompt == Prompt::SaveSearch;
+            match data.confirm_prompt() {
+                Ok(()) if saving => self.count_saved_searches(data),
+                Ok(()) => self.send_query(data),
+                Err(err) => {
+                    data.status = format!("Couldn't {}: {}", data.prompt.description(), err)
+                }
+            }
+            Handled::Yes
+        } else if let Some(query) = cmd.get(RUN_SAVED_SEARCH) {
+            data.query = query.to_string();
+            data.record_query();
+            self.send_query(data);
+            Handled::Yes
+        } else if let Some(query) = cmd.get(RECALL_QUERY) {
+            data.query = query.to_string();
+            data.show_history = false;
+            data.record_query();
+            self.send_query(data);
+            Handled::Yes
+        } else if let Some(name) = cmd.get(DELETE_SAVED_SEARCH) {
+            if let Err(err) = data.delete_saved_search(name) {
+                data.status = format!("Couldn't delete saved search: {}", err);
+            }
+            Handled::Yes
+        } else if let Some(counts) = cmd.get(SAVED_COUNTS) {
+            data.set_saved_counts(counts);
+            Handled::Yes
+        } else if cmd.is(CANCEL_PROMPT) {
+            data.cancel_prompt();
+            Handled::Yes
+        } else if cmd.is(UNDO) {
+            data.undo_last();
+            self.send_query(data);
+            Handled::Yes
+        } else if let Some(todo) = cmd.get(TOGGLE_TASK) {
+            data.toggle_task(todo);
+            self.send_query(data);
+            Handled::Yes
+        } else if let Some((path, line)) = cmd.get(OPEN_AT) {
+            let template = data.config.editor.as_deref();
+            match editor::open(template, Path::new(path.as_ref()), *line, 1) {
+                Ok(()) => data.record_recent(path),
+                Err(err) => data.status = format!("Couldn't open {}: {}", path, err),
+            }
+            Handled::Yes
+        } else if let Some(kind) = cmd.get(TOGGLE_COLUMN) {
+            data.columns.toggle(*kind);
+            Handled::Yes
+        } else if cmd.is(TOGGLE_RELATIVE_DATES) {
+            data.columns.relative_dates = !data.columns.relative_dates;
+            Handled::Yes
+        } else if let Some(sort) = cmd.get(SET_SORT) {
+            data.set_sort(*sort);
             Handled::Yes
         } else {
             Handled::No
         }
     }
+
+    fn window_removed(
+        &mut self,
+        _id: WindowId,
+        data: &mut FragmentState,
+        _env: &Env,
+        _ctx: &mut DelegateCtx,
+    ) {
+        if let Err(err) = data.session().save(&data.path) {
+            eprintln!("Couldn't save the window state: {}", err);
+        }
+    }
 }
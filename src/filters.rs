@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Local, NaiveDate};
+use druid::widget::{Button, Controller, Flex, Label, TextBox, WidgetExt};
+use druid::{Color, Data, Env, Event, EventCtx, Lens, Widget};
+use fragment_core::scope::Scope;
+
+use crate::delegate::START_SEARCH;
+use crate::FragmentState;
+
+/// The filter bar's settings, which narrow what the search walks
+#[derive(Clone, Data, Default, Lens)]
+pub struct Filters {
+    /// Folder under the root to search, or the whole root
+    pub folder: Option<Arc<str>>,
+    /// Extensions to include, e.g. `md, org`
+    pub extensions: String,
+    /// First day of the modified date range, as `YYYY-MM-DD`
+    pub modified_after: String,
+    /// Last day of the modified date range, as `YYYY-MM-DD`
+    pub modified_before: String,
+}
+
+impl Filters {
+    /// The scope to search, leaving out dates that don't parse
+    pub fn scope(&self) -> Scope {
+        Scope {
+            folder: self.folder.as_ref().map(|folder| folder.to_string()),
+            extensions: self
+                .extensions
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .map(|extension| extension.trim_start_matches('.').to_lowercase())
+                .filter(|extension| !extension.is_empty())
+                .collect(),
+            modified_after: parse_date(&self.modified_after).ok().flatten(),
+            modified_before: parse_date(&self.modified_before).ok().flatten(),
+        }
+    }
+
+    /// Why part of the filters is being ignored
+    fn problem(&self) -> Option<&'static str> {
+        if parse_date(&self.modified_after).is_err() || parse_date(&self.modified_before).is_err() {
+            Some("Dates are YYYY-MM-DD")
+        } else {
+            None
+        }
+    }
+
+    /// Limits the date range to the last `days` days, or clears it
+    fn last_days(&mut self, days: Option<i64>) {
+        self.modified_after = match days {
+            Some(days) => (Local::now().naive_local().date() - Duration::days(days))
+                .format("%Y-%m-%d")
+                .to_string(),
+            None => String::new(),
+        };
+        self.modified_before = String::new();
+    }
+}
+
+fn parse_date(text: &str) -> Result<Option<NaiveDate>, chrono::ParseError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").map(Some)
+}
+
+/// Searches again whenever the filters change
+struct SearchOnChange;
+
+impl<W: Widget<Filters>> Controller<Filters, W> for SearchOnChange {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut Filters,
+        env: &Env,
+    ) {
+        let before = data.clone();
+        child.event(ctx, event, data, env);
+        if !before.same(data) {
+            ctx.submit_command(START_SEARCH);
+        }
+    }
+}
+
+/// Extension and modified date filters, under the search box
+pub(crate) fn filter_bar() -> impl Widget<FragmentState> {
+    let field = |placeholder: &str, width: f64| {
+        TextBox::new()
+            .with_placeholder(placeholder.to_string())
+            .fix_width(width)
+    };
+    let preset = |label: &str, days: Option<i64>| {
+        Button::new(label.to_string())
+            .on_click(move |_, data: &mut Filters, _| data.last_days(days))
+    };
+
+    Flex::row()
+        .with_child(Label::new("Extensions"))
+        .with_spacer(5.0)
+        .with_child(field("md, org", 100.0).lens(Filters::extensions))
+        .with_spacer(15.0)
+        .with_child(Label::new("Modified"))
+        .with_spacer(5.0)
+        .with_child(field("from", 110.0).lens(Filters::modified_after))
+        .with_spacer(5.0)
+        .with_child(field("to", 110.0).lens(Filters::modified_before))
+        .with_spacer(5.0)
+        .with_child(preset("Today", Some(0)))
+        .with_child(preset("Week", Some(7)))
+        .with_child(preset("Month", Some(30)))
+        .with_child(preset("Any time", None))
+        .with_spacer(10.0)
+        .with_child(
+            Label::dynamic(|data: &Filters, _: &Env| data.problem().unwrap_or("").to_string())
+                .with_text_color(Color::rgb8(220, 110, 100)),
+        )
+        .controller(SearchOnChange)
+        .lens(FragmentState::filters)
+        .padding(druid::Insets::new(5.0, 0.0, 5.0, 5.0))
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use druid::widget::{Flex, Label, List, Scroll, WidgetExt};
+use druid::{theme, Color, Command, Data, Env, Lens, Target, Widget};
+use fragment_core::search::Folder;
+
+use crate::delegate::{FILTER_FOLDER, TOGGLE_FOLDER};
+use crate::search::ListItem;
+use crate::FragmentState;
+
+/// One row of the folder tree
+#[derive(Clone, Data, Lens)]
+pub struct FolderRow {
+    /// Path under the root, empty for the root itself
+    pub path: Arc<str>,
+    pub name: Arc<str>,
+    pub depth: usize,
+    /// Notes in the folder and its subfolders
+    pub notes: usize,
+    /// How many of those match the search
+    pub matching: usize,
+    pub has_children: bool,
+    pub expanded: bool,
+    pub selected: bool,
+}
+
+impl FolderRow {
+    fn count(&self) -> String {
+        if self.matching == self.notes {
+            self.notes.to_string()
+        } else {
+            format!("{}/{}", self.matching, self.notes)
+        }
+    }
+}
+
+/// The rows to show: the root, then the folders whose parents are expanded
+pub fn folder_rows(
+    folders: &[Folder],
+    results: &[ListItem],
+    expanded: &[Arc<str>],
+    selected: Option<&Arc<str>>,
+) -> Vec<FolderRow> {
+    let mut matching: HashMap<PathBuf, usize> = HashMap::new();
+    for note in results {
+        for folder in Path::new(note.folder.as_ref()).ancestors() {
+            *matching.entry(folder.to_path_buf()).or_insert(0) += 1;
+        }
+    }
+    let is_expanded = |path: &Path| {
+        path.as_os_str().is_empty() || expanded.iter().any(|open| Path::new(open.as_ref()) == path)
+    };
+
+    folders
+        .iter()
+        .enumerate()
+        .filter(|(_, folder)| {
+            let path = Path::new(&folder.path);
+            path.ancestors().skip(1).all(|parent| is_expanded(parent))
+        })
+        .map(|(i, folder)| {
+            let path = Path::new(&folder.path);
+            let has_children = folders
+                .get(i + 1)
+                .map_or(false, |next| Path::new(&next.path).parent() == Some(path));
+            FolderRow {
+                path: folder.path.as_str().into(),
+                name: match path.file_name() {
+                    Some(name) => name.to_string_lossy().into(),
+                    None => "All notes".into(),
+                },
+                depth: path.components().count(),
+                notes: folder.notes,
+                matching: matching.get(path).copied().unwrap_or(0),
+                has_children,
+                expanded: is_expanded(path),
+                selected: match selected {
+                    Some(selected) => selected.as_ref() == folder.path,
+                    None => folder.path.is_empty(),
+                },
+            }
+        })
+        .collect()
+}
+
+fn folder_row() -> impl Widget<FolderRow> {
+    let toggle = Label::new(|data: &FolderRow, _: &Env| {
+        let arrow = match (data.has_children && data.depth > 0, data.expanded) {
+            (false, _) => "  ",
+            (true, true) => "▾ ",
+            (true, false) => "▸ ",
+        };
+        format!("{}{}", "    ".repeat(data.depth.saturating_sub(1)), arrow)
+    })
+    .on_click(|ctx, data: &mut FolderRow, _| {
+        ctx.submit_command(Command::new(
+            TOGGLE_FOLDER,
+            data.path.clone(),
+            Target::Global,
+        ))
+    });
+
+    let name = Label::new(|data: &FolderRow, _: &Env| data.name.to_string())
+        .expand_width()
+        .env_scope(|env, data: &FolderRow| {
+            if data.selected {
+                env.set(theme::LABEL_COLOR, env.get(theme::PRIMARY_LIGHT));
+            }
+        })
+        .on_click(|ctx, data: &mut FolderRow, _| {
+            ctx.submit_command(Command::new(
+                FILTER_FOLDER,
+                data.path.clone(),
+                Target::Global,
+            ))
+        });
+
+    Flex::row()
+        .with_child(toggle)
+        .with_flex_child(name, 1.0)
+        .with_child(
+            Label::new(|data: &FolderRow, _: &Env| data.count())
+                .with_text_color(Color::rgb8(150, 150, 150)),
+        )
+        .padding(druid::Insets::new(5.0, 2.0, 5.0, 2.0))
+}
+
+/// The folders of the root as a tree, with how many notes each holds and how
+/// many of them match. Picking one limits the search to it, and new notes
+/// are made there.
+pub(crate) fn folder_sidebar() -> impl Widget<FragmentState> {
+    Flex::column()
+        .with_child(
+            Label::new("Folders")
+                .padding(5.0)
+                .expand_width()
+                .background(theme::PRIMARY_DARK),
+        )
+        .with_flex_child(
+            Scroll::new(List::new(|| folder_row()).lens(FragmentState::folders))
+                .vertical()
+                .expand_width(),
+            1.0,
+        )
+        .border(Color::rgb8(100, 100, 100), 1.0)
+        .rounded(5.0)
+        .padding(5.0)
+}
+
+impl FragmentState {
+    /// Limits the search to `folder`, or the whole root if it's empty
+    pub(crate) fn filter_folder(&mut self, folder: &Arc<str>) {
+        self.filters.folder = if folder.is_empty() {
+            None
+        } else {
+            Some(folder.clone())
+        };
+        self.update_folders();
+    }
+
+    /// Expands a collapsed folder, or collapses an expanded one
+    pub(crate) fn toggle_folder(&mut self, folder: &Arc<str>) {
+        let mut expanded = self.expanded_folders.as_ref().clone();
+        match expanded.iter().position(|open| open == folder) {
+            Some(i) => {
+                expanded.remove(i);
+            }
+            None => expanded.push(folder.clone()),
+        }
+        self.expanded_folders = Arc::new(expanded);
+        self.update_folders();
+    }
+
+    pub(crate) fn update_folders(&mut self) {
+        self.folders = Arc::new(folder_rows(
+            &self.folder_counts,
+            &self.results,
+            &self.expanded_folders,
+            self.filters.folder.as_ref(),
+        ));
+    }
+
+    /// Where new notes go: the selected folder, or the root
+    pub(crate) fn new_note_folder(&self) -> PathBuf {
+        let root = Path::new(&self.path);
+        match &self.filters.folder {
+            Some(folder) => root.join(folder.as_ref()),
+            None => root.to_path_buf(),
+        }
+    }
+}
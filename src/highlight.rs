@@ -0,0 +1,106 @@
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Style, Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+
+// Loading the bundled grammars and themes takes a while, so it's done once,
+// the first time a code block is shown.
+thread_local! {
+    static SYNTAXES: SyntaxSet = SyntaxSet::load_defaults_newlines();
+    static THEMES: ThemeSet = ThemeSet::load_defaults();
+}
+
+const DARK_THEME: &str = "base16-ocean.dark";
+const LIGHT_THEME: &str = "InspiredGitHub";
+
+/// A piece of highlighted code, with its color as RGB
+#[derive(Clone, Debug, PartialEq)]
+pub struct Highlighted {
+    pub text: String,
+    pub color: (u8, u8, u8),
+    pub bold: bool,
+    pub italic: bool,
+}
+
+/// Highlights `code` for the language named in a fence's info string, such
+/// as `rust`, `sh` or `sql`. `None` when the language isn't known.
+pub fn highlight(code: &str, language: &str, dark: bool) -> Option<Vec<Highlighted>> {
+    // Info strings can carry more than the language, like `rust,ignore`
+    let token = language
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+        .next()?;
+
+    SYNTAXES.with(|syntaxes| {
+        let syntax = syntaxes.find_syntax_by_token(token)?;
+        THEMES.with(|themes| {
+            let theme = theme(themes, dark)?;
+            let mut highlighter = HighlightLines::new(syntax, theme);
+            let mut spans = vec![];
+            for line in code.lines() {
+                // The newline grammars expect each line to end with one
+                let line = format!("{}\n", line);
+                for (style, text) in highlighter.highlight(&line, syntaxes) {
+                    spans.push(highlighted(style, text));
+                }
+            }
+            // The block's last newline is added between blocks instead
+            if let Some(last) = spans.last_mut() {
+                if last.text.ends_with('\n') {
+                    last.text.pop();
+                }
+            }
+            Some(spans)
+        })
+    })
+}
+
+fn theme(themes: &ThemeSet, dark: bool) -> Option<&Theme> {
+    themes
+        .themes
+        .get(if dark { DARK_THEME } else { LIGHT_THEME })
+}
+
+fn highlighted(style: Style, text: &str) -> Highlighted {
+    Highlighted {
+        text: text.to_string(),
+        color: (style.foreground.r, style.foreground.g, style.foreground.b),
+        bold: style.font_style.contains(FontStyle::BOLD),
+        italic: style.font_style.contains(FontStyle::ITALIC),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(spans: &[Highlighted]) -> String {
+        spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    #[test]
+    fn the_code_comes_back_whole_without_a_trailing_newline() {
+        let code = "fn main() {\n    let answer = 42;\n}";
+        let spans = highlight(code, "rust", true).unwrap();
+        assert_eq!(text(&spans), code);
+        assert!(spans.len() > 1);
+    }
+
+    #[test]
+    fn the_language_is_the_first_word_of_the_info_string() {
+        for info in &["rust,ignore", "rust {.numberLines}", "sh session", "RUST"] {
+            assert!(highlight("echo hi", info, false).is_some(), "{}", info);
+        }
+    }
+
+    #[test]
+    fn unknown_languages_arent_highlighted() {
+        assert_eq!(highlight("anything", "not-a-language", true), None);
+    }
+
+    #[test]
+    fn dark_and_light_themes_color_differently() {
+        let dark = highlight("fn main() {}", "rust", true).unwrap();
+        let light = highlight("fn main() {}", "rust", false).unwrap();
+        assert_eq!(text(&dark), text(&light));
+        assert_ne!(dark[0].color, light[0].color);
+    }
+}
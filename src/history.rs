@@ -0,0 +1,219 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use druid::widget::{Button, Controller, Either, Flex, Label, List, SizedBox, WidgetExt};
+use druid::{theme, Color, Command, Data, Env, Event, EventCtx, Lens, Target, TimerToken, Widget};
+use fragment_core::history::{Entry, History};
+
+use crate::columns::format_date;
+use crate::delegate::RECALL_QUERY;
+use crate::FragmentState;
+
+/// How long a query has to sit unchanged before it counts as searched for
+const SETTLE_DELAY: Duration = Duration::from_millis(1500);
+
+/// How many queries the history dropdown shows
+const SHOWN: usize = 12;
+
+/// One row of the history dropdown
+#[derive(Clone, Data, Lens)]
+pub struct HistoryRow {
+    pub query: Arc<str>,
+    /// e.g. `3× · 2 days ago`
+    pub used: Arc<str>,
+}
+
+impl From<&Entry> for HistoryRow {
+    fn from(entry: &Entry) -> Self {
+        let last_used = UNIX_EPOCH + Duration::from_secs(entry.last_used);
+        HistoryRow {
+            query: entry.query.as_str().into(),
+            used: format!("{}× · {}", entry.uses, format_date(last_used, true)).into(),
+        }
+    }
+}
+
+/// Records the query once typing in the search box has settled on it
+pub struct RecordQuery {
+    timer: TimerToken,
+}
+
+impl RecordQuery {
+    pub fn new() -> RecordQuery {
+        RecordQuery {
+            timer: TimerToken::INVALID,
+        }
+    }
+}
+
+impl Default for RecordQuery {
+    fn default() -> Self {
+        RecordQuery::new()
+    }
+}
+
+impl<W: Widget<FragmentState>> Controller<FragmentState, W> for RecordQuery {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut FragmentState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Timer(token) if *token == self.timer => {
+                self.timer = TimerToken::INVALID;
+                // Stepping through the history shouldn't reorder it
+                if !data.recalling() {
+                    data.record_query();
+                }
+            }
+            _ => {
+                let before = data.query.clone();
+                child.event(ctx, event, data, env);
+                if data.query != before {
+                    self.timer = ctx.request_timer(SETTLE_DELAY);
+                }
+            }
+        }
+    }
+}
+
+fn history_row() -> impl Widget<HistoryRow> {
+    Flex::row()
+        .with_flex_child(
+            Label::new(|data: &HistoryRow, _: &Env| data.query.to_string()).expand_width(),
+            1.0,
+        )
+        .with_child(
+            Label::new(|data: &HistoryRow, _: &Env| data.used.to_string())
+                .with_text_color(Color::rgb8(150, 150, 150)),
+        )
+        .padding(druid::Insets::new(5.0, 2.0, 5.0, 2.0))
+        .on_click(|ctx, data: &mut HistoryRow, _| {
+            ctx.submit_command(Command::new(
+                RECALL_QUERY,
+                data.query.clone(),
+                Target::Global,
+            ))
+        })
+}
+
+/// Button opening the history dropdown, beside the search box
+pub(crate) fn history_button() -> impl Widget<FragmentState> {
+    Button::new("History").on_click(|_, data: &mut FragmentState, _| data.toggle_history())
+}
+
+/// The queries searched for most and most lately, under the search box
+pub(crate) fn history_dropdown() -> impl Widget<FragmentState> {
+    let dropdown = Flex::column()
+        .with_child(
+            Flex::row()
+                .with_flex_child(Label::new("Recent searches").expand_width(), 1.0)
+                .with_child(
+                    Button::new("Clear history")
+                        .on_click(|_, data: &mut FragmentState, _| data.clear_history()),
+                )
+                .padding(5.0)
+                .background(theme::PRIMARY_DARK),
+        )
+        .with_child(
+            Either::new(
+                |data: &FragmentState, _: &Env| data.history_rows.is_empty(),
+                Label::new("Nothing searched for yet")
+                    .with_text_color(Color::rgb8(150, 150, 150))
+                    .padding(5.0),
+                List::new(|| history_row()).lens(FragmentState::history_rows),
+            )
+            .expand_width(),
+        )
+        .border(Color::rgb8(100, 100, 100), 1.0)
+        .rounded(5.0)
+        .padding(druid::Insets::new(5.0, 0.0, 5.0, 5.0));
+
+    Either::new(
+        |data: &FragmentState, _: &Env| data.show_history,
+        dropdown,
+        SizedBox::empty(),
+    )
+}
+
+impl FragmentState {
+    /// Adds the query to the history, unless it's the last thing searched for
+    pub(crate) fn record_query(&mut self) {
+        let query = self.query.trim();
+        let last = self.history.recent().first();
+        if query.is_empty() || last.map_or(false, |entry| entry.query == query) {
+            return;
+        }
+        let query = query.to_string();
+        Arc::make_mut(&mut self.history).record(&query, SystemTime::now());
+        self.history_position = None;
+        self.write_history();
+    }
+
+    /// Swaps the query for the next older, or newer, one in the history.
+    /// Going newer than the newest brings back what was being typed.
+    pub(crate) fn recall_history(&mut self, older: bool) {
+        // Typing since the last recall starts over from what's typed now
+        let position = if self.recalling() {
+            self.history_position
+        } else {
+            self.history_draft = self.query.clone();
+            None
+        };
+        let history = self.history.clone();
+        let recent = history.recent();
+        let next = match (position, older) {
+            (None, true) => recent
+                .iter()
+                .position(|entry| entry.query != self.history_draft.trim()),
+            (Some(i), true) => Some((i + 1).min(recent.len() - 1)),
+            (Some(i), false) if i > 0 => Some(i - 1),
+            (_, false) => None,
+        };
+        if next.is_none() && position.is_none() {
+            return;
+        }
+        self.history_position = next;
+        self.query = match next {
+            Some(i) => recent[i].query.clone(),
+            None => self.history_draft.clone(),
+        };
+    }
+
+    /// Whether the query is one brought back by Ctrl+Up/Down, untouched
+    fn recalling(&self) -> bool {
+        self.history_position
+            .and_then(|i| self.history.recent().get(i))
+            .map_or(false, |entry| entry.query == self.query)
+    }
+
+    pub(crate) fn toggle_history(&mut self) {
+        self.show_history = !self.show_history;
+        if self.show_history {
+            self.history_rows = Arc::new(
+                self.history
+                    .ranked(SystemTime::now())
+                    .into_iter()
+                    .take(SHOWN)
+                    .map(HistoryRow::from)
+                    .collect(),
+            );
+        }
+    }
+
+    pub(crate) fn clear_history(&mut self) {
+        self.history = Arc::new(History::default());
+        self.history_rows = Arc::new(Vec::new());
+        self.history_position = None;
+        self.write_history();
+    }
+
+    fn write_history(&mut self) {
+        if let Err(err) = self.history.save(&self.path) {
+            self.status = format!("Couldn't save search history: {}", err);
+        }
+    }
+}
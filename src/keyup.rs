@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
 use druid::widget::Controller;
-use druid::{Env, Event, EventCtx, HotKey, KbKey, Widget};
+use druid::{Env, Event, EventCtx, HotKey, KbKey, SysMods, Widget};
 
 use super::FragmentState;
 
@@ -30,11 +30,30 @@ impl<W: Widget<FragmentState>> Controller<FragmentState, W> for KeyUp<FragmentSt
             Event::WindowConnected => {
                 ctx.request_focus();
             }
+            Event::KeyDown(key_event)
+                if (HotKey::new(None, KbKey::Tab)).matches(key_event) && data.complete_tag() =>
+            {
+                ctx.set_handled();
+            }
+            Event::KeyDown(key_event)
+                if HotKey::new(SysMods::Cmd, KbKey::ArrowUp).matches(key_event) =>
+            {
+                data.recall_history(true);
+                ctx.set_handled();
+            }
+            Event::KeyDown(key_event)
+                if HotKey::new(SysMods::Cmd, KbKey::ArrowDown).matches(key_event) =>
+            {
+                data.recall_history(false);
+                ctx.set_handled();
+            }
             Event::KeyUp(key_event) => match key_event {
                 k_e if (HotKey::new(None, KbKey::Enter)).matches(k_e) => {
                     data.query = data.query.trim().to_string();
-                    data.create_note_and_open()
-                        .expect("couldn't create note and open");
+                    data.record_query();
+                    if let Err(err) = data.create_note_and_open() {
+                        data.status = format!("Couldn't open {}: {}", data.query, err);
+                    }
                 }
                 _ => {
                     ctx.submit_command(super::delegate::START_SEARCH);
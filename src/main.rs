@@ -1,27 +1,69 @@
+use std::cell::Cell;
+use std::path::Path;
+use std::rc::Rc;
 use std::sync::atomic::AtomicU64;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use argh::FromArgs;
 use crossbeam_channel::Sender;
-use open;
 
-use druid::widget::{Flex, Split};
-use druid::{AppLauncher, Data, ExtEventSink, Lens, LocalizedString, Widget, WindowDesc};
+use druid::text::RichText;
+use druid::widget::{Flex, Split, WidgetExt};
+use druid::{AppLauncher, Data, Env, ExtEventSink, Lens, LocalizedString, Widget, WindowDesc};
 
+use fragment_core::config::Config;
+use fragment_core::history::History;
+use fragment_core::index::Index;
+use fragment_core::pins::{Pins, Recent};
+use fragment_core::query::ParsedQuery;
+use fragment_core::scope::Scope;
+use fragment_core::search::{Folder, SortMethod};
+use fragment_core::session::Session;
+use fragment_core::title::TitleStrategy;
+use fragment_core::undo::UndoAction;
+use fragment_core::watch::Batch;
+use fragment_core::{editor, links, note};
+
+mod actions;
+mod cli;
+mod columns;
 mod components;
 mod delegate;
+mod filters;
+mod folders;
+mod highlight;
+mod history;
+mod pins;
+mod saved;
+mod session;
+mod tag_browser;
+mod todos;
+mod virtual_list;
 mod watch;
 
 mod search;
-use search::ListItem;
+use actions::Prompt;
+use columns::Columns;
+use filters::Filters;
+use folders::FolderRow;
+use history::HistoryRow;
+use saved::SavedRow;
+use search::{ListItem, SearchResults};
+use tag_browser::TagCount;
+use todos::{ResultsView, TodoItem};
+use watch::LiveUpdates;
 
 mod keyup;
+mod preview;
+mod shortcuts;
 
 #[derive(Debug)]
 pub enum FragmentError {
     Io(std::io::Error),
     Druid(druid::PlatformError),
     DruidExtEvent(druid::ExtEventError),
+    /// A bad query, such as an invalid regex
+    Search(String),
 }
 
 #[derive(FromArgs)]
@@ -30,43 +72,335 @@ struct FragmentArgs {
     /// path of the notes folder to search
     #[argh(option, short = 'p')]
     path: String,
+
+    /// how note titles are shown: filename, frontmatter, heading or firstline
+    #[argh(option, short = 't', default = "TitleStrategy::default()")]
+    title: TitleStrategy,
+
+    #[argh(subcommand)]
+    command: Option<Subcommand>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Subcommand {
+    Search(cli::SearchArgs),
+    Grep(cli::GrepArgs),
 }
 
 #[derive(Clone, Data, Lens)]
 struct FragmentState {
     results: Arc<Vec<ListItem>>,
+    /// Every note from the last walk, regardless of the query
+    notes: Arc<Vec<ListItem>>,
+    tags: Arc<Vec<TagCount>>,
+    /// Open tasks in the notes matching the search
+    todos: Arc<Vec<TodoItem>>,
+    /// Which of the notes, their tasks or the recent notes are listed
+    view: ResultsView,
+    /// Notes pinned to the top of the results
+    pins: Arc<Pins>,
+    /// Notes opened or previewed lately
+    recent: Arc<Recent>,
+    /// The notes in `recent` that are still around, most recent first
+    recent_notes: Arc<Vec<ListItem>>,
     query: String,
+    /// Queries searched for, kept across sessions
+    history: Arc<History>,
+    /// Where Ctrl+Up/Down has got to in the history, most recent first
+    history_position: Option<usize>,
+    /// The query as typed, before going through the history
+    history_draft: String,
+    show_history: bool,
+    /// The history dropdown's rows, best first
+    history_rows: Arc<Vec<HistoryRow>>,
+    filters: Filters,
+    /// Every folder under the root, with its note count
+    folder_counts: Arc<Vec<Folder>>,
+    /// Searches saved by name, with their match counts
+    saved_searches: Arc<Vec<SavedRow>>,
+    /// Folders open in the folder tree
+    expanded_folders: Arc<Vec<Arc<str>>>,
+    /// The folder tree's visible rows
+    folders: Arc<Vec<FolderRow>>,
     path: String,
     sender: Arc<Sender<Query>>,
     selected_note: Option<String>,
+    selected_path: Option<Arc<str>>,
+    /// `[[links]]` in the selected note
+    links: Arc<Vec<Arc<str>>>,
+    /// Notes linking to the selected note
+    backlinks: Arc<Vec<ListItem>>,
+    show_front_matter: bool,
+    /// Show the selected note's markdown as written rather than rendered
+    show_source: bool,
+    rendered_note: RichText,
+    columns: Columns,
+    #[data(same_fn = "PartialEq::eq")]
+    titles: TitleStrategy,
+    config: Arc<Config>,
+    prompt: Prompt,
+    prompt_text: String,
+    /// Notes whose links will be rewritten by the rename in progress
+    rename_referrers: Arc<Vec<ListItem>>,
+    /// File changes made this session, most recent last
+    undo: Arc<Vec<UndoAction>>,
+    /// Feedback from the last action, shown at the bottom of the window
+    status: String,
+    live_updates: LiveUpdates,
+    #[data(same_fn = "PartialEq::eq")]
+    sort: SortMethod,
+    /// How much of the height the results get, with the preview below
+    split_point: f64,
+    window_size: Option<(f64, f64)>,
+    window_position: Option<(f64, f64)>,
 }
 
 pub struct Query {
     query: String,
+    scope: Scope,
+    /// Whether to count the notes in each folder again too, which the
+    /// watcher takes care of while it's running
+    count_folders: bool,
     event_sink: ExtEventSink,
 }
 
 impl FragmentState {
-    fn new(path: String) -> FragmentState {
-        let initial_results = search::search("", &path.clone(), &AtomicU64::new(0), 1)
+    /// The app as `session` left it
+    fn new(
+        path: String,
+        titles: TitleStrategy,
+        session: Session,
+        index: Arc<Mutex<Index>>,
+    ) -> FragmentState {
+        let initial_search = |query: &str| {
+            search::search(
+                query,
+                &path.clone(),
+                &Scope::default(),
+                titles,
+                &index,
+                &AtomicU64::new(0),
+                1,
+            )
+        };
+        // Last time's query might not have been finished
+        let mut initial_results = initial_search(&session.query)
+            .or_else(|_| initial_search(""))
             .expect("Initial search failed");
+        initial_results.folders = Some(search::folders(&path));
+        let selected_note = session
+            .selected
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok());
 
-        FragmentState {
-            results: Arc::new(initial_results),
-            query: String::new(),
+        let mut state = FragmentState {
+            results: Arc::new(Vec::new()),
+            notes: Arc::new(Vec::new()),
+            tags: Arc::new(Vec::new()),
+            todos: Arc::new(Vec::new()),
+            view: ResultsView::Notes,
+            pins: Arc::new(Pins::load(&path)),
+            recent: Arc::new(Recent::load(&path)),
+            recent_notes: Arc::new(Vec::new()),
+            query: session.query,
+            history: Arc::new(History::load(&path)),
+            history_position: None,
+            history_draft: String::new(),
+            show_history: false,
+            history_rows: Arc::new(Vec::new()),
+            filters: Filters::default(),
+            folder_counts: Arc::new(Vec::new()),
+            expanded_folders: Arc::new(Vec::new()),
+            saved_searches: Arc::new(
+                fragment_core::saved::load(&path)
+                    .into_iter()
+                    .map(SavedRow::from)
+                    .collect(),
+            ),
+            folders: Arc::new(Vec::new()),
             path: path.clone(),
-            sender: search::spawn_search_thread(path),
-            selected_note: None,
+            sender: search::spawn_search_thread(path, titles, index),
+            selected_path: session
+                .selected
+                .filter(|_| selected_note.is_some())
+                .map(Arc::from),
+            selected_note,
+            links: Arc::new(Vec::new()),
+            backlinks: Arc::new(Vec::new()),
+            show_front_matter: false,
+            show_source: false,
+            rendered_note: RichText::new("".into()),
+            columns: Columns::default(),
+            titles,
+            config: Arc::new(Config::load(&path)),
+            prompt: Prompt::None,
+            prompt_text: String::new(),
+            rename_referrers: Arc::new(Vec::new()),
+            undo: Arc::new(Vec::new()),
+            status: String::new(),
+            live_updates: LiveUpdates::Starting,
+            sort: session.sort,
+            split_point: session.split_point,
+            window_size: session.window_size,
+            window_position: session.window_position,
+        };
+        state.set_results(initial_results);
+        state
+    }
+
+    fn set_results(&mut self, results: SearchResults) {
+        self.tags = Arc::new(tag_browser::tag_counts(&results.notes));
+        self.notes = results.notes;
+        let mut matches = results.matches;
+        fragment_core::search::sort(&mut matches, self.sort);
+        self.results = Arc::new(self.mark_pins(matches));
+        self.todos = Arc::new(todos::open_todos(&self.results));
+        if let Some(folders) = results.folders {
+            self.folder_counts = folders;
+        }
+        self.update_folders();
+        self.update_recent();
+        self.update_links();
+    }
+
+    /// Re-reads just the notes a batch of file changes touched, rather than
+    /// walking the whole folder again
+    fn apply_changes(&mut self, batch: &Batch, env: &Env) {
+        let updated = fragment_core::search::apply_changes(
+            &self.query,
+            &self.path,
+            &self.filters.scope(),
+            self.titles,
+            batch,
+            &self.notes,
+            &self.results,
+        );
+        match updated {
+            Ok((notes, matches)) => {
+                // Any note coming or going changes the folder counts, but
+                // an edit in place doesn't
+                let counts_changed = !batch.removed.is_empty()
+                    || batch.changed.iter().any(|path| {
+                        !self
+                            .notes
+                            .iter()
+                            .any(|note| Path::new(note.path.as_ref()) == path)
+                    });
+                let folders = if counts_changed {
+                    Some(search::folders(&self.path))
+                } else {
+                    None
+                };
+                self.set_results(SearchResults {
+                    matches,
+                    notes: Arc::new(notes),
+                    folders,
+                })
+            }
+            Err(err) => self.status = format!("Couldn't update results: {}", err),
+        }
+
+        // Keep the preview in step with edits made elsewhere
+        if let Some(path) = self.selected_path.clone() {
+            if batch.affects(Path::new(path.as_ref())) {
+                if let Ok(contents) = std::fs::read_to_string(path.as_ref()) {
+                    self.selected_note = Some(contents);
+                    self.render_preview(env);
+                }
+            }
+        }
+    }
+
+    fn selected_item(&self) -> Option<&ListItem> {
+        let path = self.selected_path.as_ref()?;
+        self.notes.iter().find(|note| &note.path == path)
+    }
+
+    fn update_links(&mut self) {
+        let (links, backlinks) = match self.selected_item() {
+            Some(note) => (note.links.clone(), links::backlinks(note, &self.notes)),
+            None => (Arc::new(Vec::new()), vec![]),
+        };
+        self.links = links;
+        self.backlinks = Arc::new(backlinks);
+    }
+
+    /// Known tags that complete a `tag:` filter being typed at the end of the query
+    fn tag_completions(&self) -> Vec<Arc<str>> {
+        let partial = match self.query.rsplit(' ').next() {
+            Some(word) if word.starts_with("tag:") => word["tag:".len()..].to_lowercase(),
+            _ => return vec![],
+        };
+        self.tags
+            .iter()
+            .filter(|tag| tag.name.len() > partial.len())
+            .filter(|tag| tag.name.to_lowercase().starts_with(&partial))
+            .map(|tag| tag.name.clone())
+            .take(5)
+            .collect()
+    }
+
+    /// Completes the `tag:` filter at the end of the query with the first
+    /// suggestion, returning whether there was anything to complete.
+    fn complete_tag(&mut self) -> bool {
+        match self.tag_completions().first() {
+            Some(tag) => {
+                let start = self.query.rfind(' ').map(|i| i + 1).unwrap_or(0);
+                self.query.truncate(start);
+                self.query.push_str(&format!("tag:{}", tag));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn add_tag_filter(&mut self, tag: &str) {
+        let filter = format!("tag:{}", tag);
+        if self.query.split_whitespace().any(|word| word == filter) {
+            return;
+        }
+        if !self.query.trim().is_empty() {
+            self.query = format!("{} {}", self.query.trim_end(), filter);
+        } else {
+            self.query = filter;
         }
     }
 
-    fn create_note_and_open(&self) -> Result<(), FragmentError> {
-        let file_with_path = std::path::Path::new(&self.path)
-            .join(self.query.clone())
-            .with_extension("md");
-        std::fs::File::create(&file_with_path).map_err(FragmentError::Io)?;
-        open::that(file_with_path).map_err(FragmentError::Io)?;
-        Ok(())
+    /// Opens or creates the note named by the query's search words, leaving
+    /// out any `key:value` filters
+    fn create_note_and_open(&self) -> std::io::Result<()> {
+        let query = ParsedQuery::parse_for(
+            &self.query,
+            self.notes.iter().map(|item| item.note.as_ref()),
+        );
+        self.open_or_create_note(&query.pattern)
+    }
+
+    /// Opens the note called `title` in the editor, creating it first if
+    /// there's no such file yet.
+    fn open_or_create_note(&self, title: &str) -> std::io::Result<()> {
+        let file_with_path = note::create(
+            &self.new_note_folder(),
+            title,
+            &self.config.note_extension,
+            self.titles,
+        )?;
+        editor::open(self.config.editor.as_deref(), &file_with_path, 1, 1)
+    }
+
+    /// Opens whichever indexed note a `[[link]]` resolves to, or creates a
+    /// new note named after the link. Only the notes already walked are
+    /// looked at, so following a link never reads the whole folder.
+    fn open_link(&self, target: &str) -> std::io::Result<()> {
+        match self
+            .notes
+            .iter()
+            .find(|note| links::resolves_to(target, note))
+        {
+            Some(note) => note.open_note_in_editor(self.config.editor.as_deref()),
+            None => self.open_or_create_note(target),
+        }
     }
 }
 
@@ -75,40 +409,101 @@ fn main() -> Result<(), FragmentError> {
     let args: FragmentArgs = argh::from_env();
     let path = args.path;
 
-    let main_window = WindowDesc::new(ui_builder)
+    if let Some(command) = args.command {
+        let found = match command {
+            Subcommand::Search(search) => cli::search(&path, args.title, search)?,
+            Subcommand::Grep(grep) => cli::grep(&path, args.title, grep)?,
+        };
+        std::process::exit(if found { 0 } else { 1 });
+    }
+
+    let session = Session::load(&path);
+    let split_point = session.split_point;
+    let mut main_window = WindowDesc::new(move || ui_builder(split_point))
         .title(LocalizedString::new("").with_placeholder("Fragment 0.1"));
+    if let Some(size) = session.window_size {
+        main_window = main_window.window_size(size);
+    }
+    if let Some(position) = session.window_position {
+        main_window = main_window.set_position(position);
+    }
     let launcher = AppLauncher::with_window(main_window);
     let event_sink = launcher.get_external_handle();
 
+    // Notes as they were last read, shared by the searches and forgetting
+    // whatever the watcher says changed
+    let index = Arc::new(Mutex::new(Index::new()));
+
     let delegate = delegate::Delegate {
         event_sink: event_sink.clone(),
+        index: index.clone(),
     };
 
     // Fire up a thread to notify of changes at the root path
-    let _watch = watch::watch(path.clone(), event_sink.clone());
+    watch::watch(
+        path.clone(),
+        &Config::load(&path),
+        index.clone(),
+        event_sink.clone(),
+    );
+
+    let state = FragmentState::new(path.clone(), args.title, session, index.clone());
+    saved::spawn_counts(
+        path,
+        args.title,
+        index,
+        &state.saved_searches,
+        event_sink.clone(),
+    );
 
     launcher
         .delegate(delegate)
-        .launch(FragmentState::new(path))
+        .launch(state)
         .map_err(FragmentError::Druid)?;
 
     Ok(())
 }
 
-fn ui_builder() -> impl Widget<FragmentState> {
+fn ui_builder(split_point: f64) -> impl Widget<FragmentState> {
+    let top_height = Rc::new(Cell::new(0.0));
+    let bottom_height = Rc::new(Cell::new(0.0));
+
     Flex::column()
         // Search box. Automatically gains focus on launch
         .with_child(components::search_box())
+        .with_child(history::history_dropdown())
+        .with_child(filters::filter_bar())
+        .with_child(components::tag_completions())
+        // Input for renaming or moving the selected note
+        .with_child(components::prompt_bar())
         // The rest of the app
         .with_flex_child(
-            Split::rows(
-                // Search results
-                components::top_pane(),
-                // File preview (TODO)
-                components::text_pane(),
-            )
-            .split_point(0.8)
-            .draggable(true),
+            Flex::row()
+                .with_child(
+                    Flex::column()
+                        .with_flex_child(saved::saved_sidebar(), 1.0)
+                        .with_flex_child(folders::folder_sidebar(), 1.0)
+                        .with_flex_child(tag_browser::tag_sidebar(), 1.0)
+                        .fix_width(200.0),
+                )
+                .with_flex_child(
+                    Split::rows(
+                        // Search results
+                        session::Measure::new(components::top_pane(), top_height.clone()),
+                        // File preview (TODO)
+                        session::Measure::new(components::text_pane(), bottom_height.clone()),
+                    )
+                    .split_point(split_point)
+                    .draggable(true)
+                    .controller(session::RememberSplit {
+                        top: top_height,
+                        bottom: bottom_height,
+                    }),
+                    1.0,
+                ),
             1.0,
         )
+        .with_child(components::status_bar())
+        .controller(shortcuts::Shortcuts)
+        .controller(session::RememberWindow)
 }
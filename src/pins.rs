@@ -0,0 +1,51 @@
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::search::ListItem;
+use crate::FragmentState;
+
+impl FragmentState {
+    /// Pins `note` to the top of the results, or unpins it
+    pub(crate) fn toggle_pin(&mut self, note: &ListItem) -> std::io::Result<()> {
+        let pinned = Arc::make_mut(&mut self.pins).toggle(&self.path, note);
+        self.pins.save(&self.path)?;
+        self.status = if pinned {
+            format!("Pinned {}", note.title)
+        } else {
+            format!("Unpinned {}", note.title)
+        };
+        self.results = Arc::new(self.mark_pins(self.results.as_ref().clone()));
+        self.update_recent();
+        Ok(())
+    }
+
+    /// Flags the pinned notes in `list` and moves them to the top
+    pub(crate) fn mark_pins(&self, mut list: Vec<ListItem>) -> Vec<ListItem> {
+        for item in list.iter_mut() {
+            item.pinned = self.pins.is_pinned(&self.path, item);
+        }
+        self.pins.pinned_first(&self.path, &mut list);
+        list
+    }
+
+    /// Notes that the note at `path` was just opened or previewed
+    pub(crate) fn record_recent(&mut self, path: &Arc<str>) {
+        let note = match self.notes.iter().find(|note| &note.path == path) {
+            Some(note) => note.clone(),
+            None => return,
+        };
+        Arc::make_mut(&mut self.recent).record(&self.path, &note, SystemTime::now());
+        if let Err(err) = self.recent.save(&self.path) {
+            self.status = format!("Couldn't save recent notes: {}", err);
+        }
+        self.update_recent();
+    }
+
+    pub(crate) fn update_recent(&mut self) {
+        let mut recent = self.recent.notes(&self.path, &self.notes[..]);
+        for item in recent.iter_mut() {
+            item.pinned = self.pins.is_pinned(&self.path, item);
+        }
+        self.recent_notes = Arc::new(recent);
+    }
+}
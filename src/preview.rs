@@ -0,0 +1,202 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use druid::text::{RichText, RichTextBuilder};
+use druid::{theme, Color, Command, Env, FontFamily, FontStyle, FontWeight, Target};
+use fragment_core::format::{self, NoteFormat};
+use fragment_core::markdown::{Block, Link, Span};
+
+use crate::delegate::{OPEN_LINK, OPEN_URL};
+use crate::highlight;
+use crate::FragmentState;
+
+const QUOTE_COLOR: Color = Color::rgb8(170, 170, 170);
+const CODE_COLOR: Color = Color::rgb8(220, 180, 120);
+
+/// Font sizes for heading levels 1 to 6
+const HEADING_SIZES: [f64; 6] = [26.0, 22.0, 19.0, 17.0, 15.0, 14.0];
+
+/// Lays out parsed markdown as a single block of rich text. `dark` picks the
+/// code highlighting theme to suit the app's background.
+pub fn render(blocks: &[Block], dark: bool) -> RichText {
+    let mut builder = RichTextBuilder::new();
+
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            // Items in a list sit directly under each other
+            let list_continues = matches!(
+                (&blocks[i - 1], block),
+                (Block::ListItem { .. }, Block::ListItem { .. })
+            );
+            builder.push(if list_continues { "\n" } else { "\n\n" });
+        }
+
+        match block {
+            Block::Heading { level, spans } => {
+                let size = HEADING_SIZES[level.saturating_sub(1).min(5)];
+                for span in spans {
+                    push_span(&mut builder, span)
+                        .size(size)
+                        .weight(FontWeight::BOLD);
+                }
+            }
+            Block::Paragraph(spans) => {
+                for span in spans {
+                    push_span(&mut builder, span);
+                }
+            }
+            Block::CodeBlock { language, code } => {
+                let highlighted = language
+                    .as_ref()
+                    .and_then(|language| highlight::highlight(code, language, dark));
+                match highlighted {
+                    Some(spans) => {
+                        for span in spans {
+                            let (r, g, b) = span.color;
+                            let mut text = builder.push(&span.text);
+                            text.font_family(FontFamily::MONOSPACE)
+                                .text_color(Color::rgb8(r, g, b));
+                            if span.bold {
+                                text.weight(FontWeight::BOLD);
+                            }
+                            if span.italic {
+                                text.style(FontStyle::Italic);
+                            }
+                        }
+                    }
+                    None => {
+                        builder
+                            .push(code)
+                            .font_family(FontFamily::MONOSPACE)
+                            .text_color(CODE_COLOR);
+                    }
+                }
+            }
+            Block::ListItem {
+                depth,
+                marker,
+                checked,
+                spans,
+            } => {
+                let bullet = match (checked, marker.as_str()) {
+                    (Some(true), _) => "☑".to_string(),
+                    (Some(false), _) => "☐".to_string(),
+                    (None, "-") => "•".to_string(),
+                    (None, number) => number.to_string(),
+                };
+                builder.push(&format!("{}{} ", "    ".repeat(*depth), bullet));
+                for span in spans {
+                    push_span(&mut builder, span);
+                }
+            }
+            Block::Quote(spans) => {
+                builder.push("▍ ").text_color(QUOTE_COLOR);
+                for span in spans {
+                    push_span(&mut builder, span)
+                        .style(FontStyle::Italic)
+                        .text_color(QUOTE_COLOR);
+                }
+            }
+            Block::Rule => {
+                builder.push(&"─".repeat(40)).text_color(QUOTE_COLOR);
+            }
+        }
+    }
+
+    builder.build()
+}
+
+fn push_span<'a>(
+    builder: &'a mut RichTextBuilder,
+    span: &Span,
+) -> druid::text::AttributesAdder<'a> {
+    let mut text = builder.push(&span.text);
+    if span.style.bold {
+        text.weight(FontWeight::BOLD);
+    }
+    if span.style.italic {
+        text.style(FontStyle::Italic);
+    }
+    if span.style.code {
+        text.font_family(FontFamily::MONOSPACE)
+            .text_color(CODE_COLOR);
+    }
+    if span.style.strikethrough {
+        text.text_color(QUOTE_COLOR);
+    }
+    match &span.style.link {
+        Some(Link::Note(target)) => {
+            let target: Arc<str> = target.as_str().into();
+            text.underline(true)
+                .text_color(theme::PRIMARY_LIGHT)
+                .link(Command::new(OPEN_LINK, target, Target::Global));
+        }
+        Some(Link::Url(url)) => {
+            let url: Arc<str> = url.as_str().into();
+            text.underline(true)
+                .text_color(theme::PRIMARY_LIGHT)
+                .link(Command::new(OPEN_URL, url, Target::Global));
+        }
+        None => {}
+    }
+    text
+}
+
+fn is_dark(color: &Color) -> bool {
+    let rgba = color.as_rgba_u32();
+    let (r, g, b) = (rgba >> 24, (rgba >> 16) & 0xff, (rgba >> 8) & 0xff);
+    // Perceived brightness, weighted towards green as the eye is
+    r * 299 + g * 587 + b * 114 < 128 * 1000
+}
+
+impl FragmentState {
+    /// The format of the selected note
+    pub(crate) fn selected_format(&self) -> &'static dyn NoteFormat {
+        let path = self.selected_path.as_deref().unwrap_or("");
+        format::for_path(Path::new(path))
+    }
+
+    /// The selected note without its metadata
+    pub(crate) fn selected_body(&self) -> Option<&str> {
+        let note = self.selected_note.as_ref()?;
+        Some(self.selected_format().split_metadata(note).1)
+    }
+
+    /// Re-renders the selected note, after it changes or the front matter is
+    /// shown or hidden
+    pub(crate) fn render_preview(&mut self, env: &Env) {
+        let note = match &self.selected_note {
+            Some(note) => note,
+            None => return,
+        };
+        let format = self.selected_format();
+        let (_, body) = format.split_metadata(note);
+        let mut blocks = format.render(body);
+        if self.show_front_matter && body.len() != note.len() {
+            // Metadata isn't markup, so it's shown as it's written
+            blocks.insert(
+                0,
+                Block::CodeBlock {
+                    language: None,
+                    code: note[..note.len() - body.len()].trim_end().to_string(),
+                },
+            );
+        }
+        self.rendered_note = render(&blocks, is_dark(&env.get(theme::WINDOW_BACKGROUND_COLOR)));
+    }
+
+    /// Follows a `[text](url)` link. Links without a scheme are files
+    /// relative to the selected note.
+    pub(crate) fn open_url(&self, url: &str) -> std::io::Result<()> {
+        if url.contains("://") || url.starts_with("mailto:") {
+            return open::that(url).map(|_| ());
+        }
+        let folder = self
+            .selected_path
+            .as_ref()
+            .and_then(|path| Path::new(path.as_ref()).parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| self.path.clone().into());
+        let path = url.split('#').next().unwrap_or(url).replace("%20", " ");
+        open::that(folder.join(path)).map(|_| ())
+    }
+}
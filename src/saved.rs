@@ -0,0 +1,186 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use druid::widget::{Flex, Label, List, Scroll, WidgetExt};
+use druid::{theme, Color, Command, Data, Env, ExtEventSink, Lens, Target, Widget};
+use fragment_core::index::Index;
+use fragment_core::saved::{self, SavedSearch};
+use fragment_core::title::TitleStrategy;
+
+use crate::actions::Prompt;
+use crate::delegate::{DELETE_SAVED_SEARCH, FOCUS_PROMPT, RUN_SAVED_SEARCH, SAVED_COUNTS};
+use crate::FragmentState;
+
+/// One row of the saved searches sidebar
+#[derive(Clone, Data, Lens)]
+pub struct SavedRow {
+    pub name: Arc<str>,
+    pub query: Arc<str>,
+    /// Matching notes, `None` while counting or if the query is invalid
+    pub count: Option<usize>,
+}
+
+impl SavedRow {
+    fn saved(&self) -> SavedSearch {
+        SavedSearch {
+            name: self.name.to_string(),
+            query: self.query.to_string(),
+        }
+    }
+}
+
+impl From<SavedSearch> for SavedRow {
+    fn from(saved: SavedSearch) -> Self {
+        SavedRow {
+            name: saved.name.into(),
+            query: saved.query.into(),
+            count: None,
+        }
+    }
+}
+
+/// Counts the matches under `root` for each saved search in the background,
+/// sending the counts back by name
+pub fn spawn_counts(
+    root: String,
+    titles: TitleStrategy,
+    index: Arc<Mutex<Index>>,
+    rows: &[SavedRow],
+    event_sink: ExtEventSink,
+) {
+    let searches: Vec<SavedSearch> = rows.iter().map(SavedRow::saved).collect();
+    thread::spawn(move || {
+        let counts = saved::counts(&root, titles, &index, &searches);
+        let named = searches
+            .into_iter()
+            .map(|saved| Arc::from(saved.name))
+            .zip(counts)
+            .collect();
+        let _ = event_sink.submit_command(SAVED_COUNTS, named, Target::Global);
+    });
+}
+
+fn saved_row() -> impl Widget<SavedRow> {
+    let name = Label::new(|data: &SavedRow, _: &Env| data.name.to_string())
+        .expand_width()
+        .on_click(|ctx, data: &mut SavedRow, _| {
+            ctx.submit_command(Command::new(
+                RUN_SAVED_SEARCH,
+                data.query.clone(),
+                Target::Global,
+            ))
+        });
+    let count = Label::new(|data: &SavedRow, _: &Env| match data.count {
+        Some(count) => count.to_string(),
+        None => "–".to_string(),
+    })
+    .with_text_color(Color::rgb8(150, 150, 150));
+    let delete = Label::new("×")
+        .with_text_color(Color::rgb8(150, 150, 150))
+        .padding(druid::Insets::new(5.0, 0.0, 0.0, 0.0))
+        .on_click(|ctx, data: &mut SavedRow, _| {
+            ctx.submit_command(Command::new(
+                DELETE_SAVED_SEARCH,
+                data.name.clone(),
+                Target::Global,
+            ))
+        });
+
+    Flex::row()
+        .with_flex_child(name, 1.0)
+        .with_child(count)
+        .with_child(delete)
+        .padding(druid::Insets::new(5.0, 2.0, 5.0, 2.0))
+}
+
+/// Saved searches with how many notes each matches. Click one, or press
+/// Ctrl+1 to Ctrl+9, to run it.
+pub(crate) fn saved_sidebar() -> impl Widget<FragmentState> {
+    Flex::column()
+        .with_child(
+            Flex::row()
+                .with_flex_child(Label::new("Saved searches").expand_width(), 1.0)
+                .with_child(
+                    Label::new("+").on_click(|ctx, data: &mut FragmentState, _| {
+                        data.start_saving_search();
+                        ctx.submit_command(FOCUS_PROMPT);
+                    }),
+                )
+                .padding(5.0)
+                .background(theme::PRIMARY_DARK),
+        )
+        .with_flex_child(
+            Scroll::new(List::new(|| saved_row()).lens(FragmentState::saved_searches))
+                .vertical()
+                .expand_width(),
+            1.0,
+        )
+        .border(Color::rgb8(100, 100, 100), 1.0)
+        .rounded(5.0)
+        .padding(5.0)
+}
+
+impl FragmentState {
+    /// Asks for a name to save the current query under
+    pub(crate) fn start_saving_search(&mut self) {
+        self.prompt_text = self.query.trim().to_string();
+        self.prompt = Prompt::SaveSearch;
+    }
+
+    /// Saves the current query as `name`, replacing any search already
+    /// saved with that name
+    pub(crate) fn save_search(&mut self, name: &str) -> std::io::Result<()> {
+        let name: Arc<str> = name.trim().into();
+        if name.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "saved searches need a name",
+            ));
+        }
+        let mut rows = self.saved_searches.as_ref().clone();
+        let row = SavedRow {
+            name: name.clone(),
+            query: self.query.trim().into(),
+            count: None,
+        };
+        match rows.iter().position(|saved| saved.name == name) {
+            Some(i) => rows[i] = row,
+            None => rows.push(row),
+        }
+        self.write_saved_searches(rows)?;
+        self.status = format!("Saved search {}", name);
+        Ok(())
+    }
+
+    pub(crate) fn delete_saved_search(&mut self, name: &Arc<str>) -> std::io::Result<()> {
+        let rows = self
+            .saved_searches
+            .iter()
+            .filter(|saved| &saved.name != name)
+            .cloned()
+            .collect();
+        self.write_saved_searches(rows)
+    }
+
+    fn write_saved_searches(&mut self, rows: Vec<SavedRow>) -> std::io::Result<()> {
+        let searches: Vec<SavedSearch> = rows.iter().map(SavedRow::saved).collect();
+        saved::save(&self.path, &searches)?;
+        self.saved_searches = Arc::new(rows);
+        Ok(())
+    }
+
+    pub(crate) fn set_saved_counts(&mut self, counts: &[(Arc<str>, Option<usize>)]) {
+        let rows = self
+            .saved_searches
+            .iter()
+            .map(|row| SavedRow {
+                count: counts
+                    .iter()
+                    .find(|(name, _)| name == &row.name)
+                    .and_then(|(_, count)| *count),
+                ..row.clone()
+            })
+            .collect();
+        self.saved_searches = Arc::new(rows);
+    }
+}
@@ -0,0 +1,123 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use druid::widget::prelude::*;
+use druid::widget::{Controller, Split};
+use fragment_core::search::SortMethod;
+use fragment_core::session::Session;
+
+use crate::FragmentState;
+
+/// Passes everything through to its child, noting the height it's laid out
+/// at so a `RememberSplit` can work out where the split is.
+pub struct Measure<W> {
+    inner: W,
+    height: Rc<Cell<f64>>,
+}
+
+impl<W> Measure<W> {
+    pub fn new(inner: W, height: Rc<Cell<f64>>) -> Self {
+        Measure { inner, height }
+    }
+}
+
+impl<T: Data, W: Widget<T>> Widget<T> for Measure<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.inner.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.inner.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = self.inner.layout(ctx, bc, data, env);
+        self.height.set(size.height);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.inner.paint(ctx, data, env)
+    }
+}
+
+/// Keeps `split_point` in step with the results/preview split once it's
+/// dragged. `Split` doesn't say where its bar is, so the panes' heights are
+/// measured instead.
+pub struct RememberSplit {
+    pub top: Rc<Cell<f64>>,
+    pub bottom: Rc<Cell<f64>>,
+}
+
+impl Controller<FragmentState, Split<FragmentState>> for RememberSplit {
+    fn event(
+        &mut self,
+        child: &mut Split<FragmentState>,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut FragmentState,
+        env: &Env,
+    ) {
+        child.event(ctx, event, data, env);
+        if let Event::MouseUp(_) = event {
+            let (top, bottom) = (self.top.get(), self.bottom.get());
+            if top + bottom > 0.0 {
+                data.split_point = top / (top + bottom);
+            }
+        }
+    }
+}
+
+/// Keeps track of the window's size and position, and restores the preview
+/// of the note selected last time once there's a window to render it for.
+pub struct RememberWindow;
+
+impl<W: Widget<FragmentState>> Controller<FragmentState, W> for RememberWindow {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut FragmentState,
+        env: &Env,
+    ) {
+        match event {
+            Event::WindowConnected => data.render_preview(env),
+            Event::WindowSize(size) => data.window_size = Some((size.width, size.height)),
+            _ => {}
+        }
+        // Moving a window doesn't send an event, so the position is read
+        // when it's resized and when it's about to close
+        if let Event::WindowSize(_) | Event::WindowCloseRequested = event {
+            let position = ctx.window().get_position();
+            data.window_position = Some((position.x, position.y));
+        }
+        child.event(ctx, event, data, env)
+    }
+}
+
+impl FragmentState {
+    /// What to open with next time
+    pub(crate) fn session(&self) -> Session {
+        Session {
+            window_size: self.window_size,
+            window_position: self.window_position,
+            split_point: self.split_point,
+            sort: self.sort,
+            query: self.query.clone(),
+            selected: self.selected_path.as_ref().map(|path| path.to_string()),
+        }
+    }
+
+    pub(crate) fn set_sort(&mut self, sort: SortMethod) {
+        self.sort = sort;
+        let mut results = self.results.as_ref().clone();
+        fragment_core::search::sort(&mut results, sort);
+        self.results = Arc::new(self.mark_pins(results));
+    }
+}
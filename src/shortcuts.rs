@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use druid::widget::Controller;
+use druid::{Command, Env, Event, EventCtx, HotKey, KbKey, SysMods, Target, Widget};
+
+use crate::actions::{NoteAction, Prompt};
+use crate::delegate::{
+    CANCEL_PROMPT, CONFIRM_PROMPT, FOCUS_PROMPT, NOTE_ACTION, RUN_SAVED_SEARCH, UNDO,
+};
+use crate::FragmentState;
+
+/// Window-wide keyboard shortcuts, seen before the focused widget gets them.
+/// Focus is nearly always in the search box, so none of them are keys a text
+/// box edits with: note actions all take Ctrl+Shift, and undoing a file
+/// change takes Ctrl+Alt+Z, leaving Ctrl+Z to the text.
+pub struct Shortcuts;
+
+impl Shortcuts {
+    fn note_action(key_event: &druid::KeyEvent) -> Option<NoteAction> {
+        if HotKey::new(SysMods::CmdShift, "O").matches(key_event) {
+            Some(NoteAction::Open)
+        } else if HotKey::new(None, KbKey::F2).matches(key_event) {
+            Some(NoteAction::Rename)
+        } else if HotKey::new(SysMods::CmdShift, KbKey::Backspace).matches(key_event) {
+            Some(NoteAction::Delete)
+        } else if HotKey::new(SysMods::CmdShift, "E").matches(key_event) {
+            Some(NoteAction::Archive)
+        } else if HotKey::new(SysMods::CmdShift, "M").matches(key_event) {
+            Some(NoteAction::Move)
+        } else if HotKey::new(SysMods::CmdShift, "D").matches(key_event) {
+            Some(NoteAction::Duplicate)
+        } else if HotKey::new(SysMods::CmdShift, "R").matches(key_event) {
+            Some(NoteAction::Reveal)
+        } else if HotKey::new(SysMods::CmdShift, "P").matches(key_event) {
+            Some(NoteAction::Pin)
+        } else {
+            None
+        }
+    }
+}
+
+impl Shortcuts {
+    /// The query of the saved search Ctrl+1 to Ctrl+9 picks
+    fn saved_search(key_event: &druid::KeyEvent, data: &FragmentState) -> Option<Arc<str>> {
+        let n = (1..=9)
+            .find(|n| HotKey::new(SysMods::Cmd, n.to_string().as_str()).matches(key_event))?;
+        data.saved_searches
+            .get(n - 1)
+            .map(|saved| saved.query.clone())
+    }
+}
+
+impl<W: Widget<FragmentState>> Controller<FragmentState, W> for Shortcuts {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut FragmentState,
+        env: &Env,
+    ) {
+        if let Event::KeyDown(key_event) = event {
+            let prompting = data.prompt != Prompt::None;
+            // A rename or move being typed takes the keys for itself
+            let action = Shortcuts::note_action(key_event).filter(|_| !prompting);
+            let command: Option<Command> = match (action, &data.selected_path) {
+                (Some(action), Some(path)) => Some(Command::new(
+                    NOTE_ACTION,
+                    (action, path.clone()),
+                    Target::Global,
+                )),
+                _ if prompting && HotKey::new(None, KbKey::Escape).matches(key_event) => {
+                    Some(CANCEL_PROMPT.into())
+                }
+                _ if prompting && HotKey::new(None, KbKey::Enter).matches(key_event) => {
+                    Some(CONFIRM_PROMPT.into())
+                }
+                _ if HotKey::new(SysMods::AltCmd, "z").matches(key_event) => Some(UNDO.into()),
+                _ if HotKey::new(SysMods::CmdShift, "S").matches(key_event) => {
+                    data.start_saving_search();
+                    Some(FOCUS_PROMPT.into())
+                }
+                _ => Shortcuts::saved_search(key_event, data)
+                    .map(|query| Command::new(RUN_SAVED_SEARCH, query, Target::Global)),
+            };
+
+            if let Some(command) = command {
+                ctx.submit_command(command);
+                ctx.set_handled();
+                return;
+            }
+        }
+        child.event(ctx, event, data, env)
+    }
+}
+
+/// Moves keyboard focus to the prompt's text box when an action asks for input
+pub struct PromptFocus;
+
+impl<W: Widget<String>> Controller<String, W> for PromptFocus {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut String,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(FOCUS_PROMPT) => ctx.request_focus(),
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
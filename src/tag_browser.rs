@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use druid::widget::{Flex, Label, List, Scroll, WidgetExt};
+use druid::{theme, Color, Command, Data, Env, Lens, Target, Widget};
+use fragment_core::tags;
+
+use crate::delegate::FILTER_TAG;
+use crate::search::ListItem;
+use crate::FragmentState;
+
+/// One row of the tag sidebar
+#[derive(Clone, Data, Lens)]
+pub struct TagCount {
+    /// Full tag path, e.g. `project/alpha`
+    pub name: Arc<str>,
+    pub count: usize,
+}
+
+impl TagCount {
+    fn depth(&self) -> usize {
+        self.name.matches('/').count()
+    }
+
+    fn leaf(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+}
+
+pub fn tag_counts(notes: &[ListItem]) -> Vec<TagCount> {
+    tags::count(notes.iter().map(|note| note.tags.as_slice()))
+        .into_iter()
+        .map(|(name, count)| TagCount {
+            name: name.into(),
+            count,
+        })
+        .collect()
+}
+
+fn tag_row() -> impl Widget<TagCount> {
+    Label::new(|data: &TagCount, _: &Env| {
+        format!(
+            "{}#{}  {}",
+            "    ".repeat(data.depth()),
+            data.leaf(),
+            data.count
+        )
+    })
+    .padding(druid::Insets::new(5.0, 2.0, 5.0, 2.0))
+    .expand_width()
+    .on_click(|ctx, data: &mut TagCount, _| {
+        ctx.submit_command(Command::new(FILTER_TAG, data.name.clone(), Target::Global))
+    })
+}
+
+pub(crate) fn tag_sidebar() -> impl Widget<FragmentState> {
+    Flex::column()
+        .with_child(
+            Label::new("Tags")
+                .padding(5.0)
+                .expand_width()
+                .background(theme::PRIMARY_DARK),
+        )
+        .with_flex_child(
+            Scroll::new(List::new(|| tag_row()).lens(FragmentState::tags))
+                .vertical()
+                .expand_width(),
+            1.0,
+        )
+        .border(Color::rgb8(100, 100, 100), 1.0)
+        .rounded(5.0)
+        .padding(5.0)
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use druid::widget::{Flex, Label, List, Scroll, WidgetExt};
+use druid::{theme, Color, Command, Data, Env, Lens, Target, Widget};
+use fragment_core::tasks::{self, Task};
+
+use crate::delegate::{OPEN_AT, TOGGLE_TASK};
+use crate::search::ListItem;
+use crate::FragmentState;
+
+/// What the results pane lists
+#[derive(Clone, Copy, Data, Debug, PartialEq)]
+pub enum ResultsView {
+    /// Notes matching the search
+    Notes,
+    /// Open tasks in those notes
+    Todos,
+    /// Notes opened or previewed lately, whatever the search
+    Recent,
+}
+
+/// One row of the todos view: an open task and the note it's in
+#[derive(Clone, Data, Lens)]
+pub struct TodoItem {
+    pub path: Arc<str>,
+    pub title: Arc<str>,
+    pub line: u64,
+    pub text: Arc<str>,
+}
+
+/// The open tasks in `notes`, in the notes' order
+pub fn open_todos(notes: &[ListItem]) -> Vec<TodoItem> {
+    notes
+        .iter()
+        .flat_map(|note| {
+            note.tasks
+                .iter()
+                .filter(|task| !task.done)
+                .map(move |task| TodoItem {
+                    path: note.path.clone(),
+                    title: note.title.clone(),
+                    line: task.line,
+                    text: task.text.clone(),
+                })
+        })
+        .collect()
+}
+
+fn todo_row() -> impl Widget<TodoItem> {
+    let checkbox = Label::new("☐")
+        .padding(druid::Insets::new(5.0, 2.0, 5.0, 2.0))
+        .on_click(|ctx, data: &mut TodoItem, _| {
+            ctx.submit_command(Command::new(TOGGLE_TASK, data.clone(), Target::Global))
+        });
+    let source = Label::new(|data: &TodoItem, _: &Env| format!("{}:{}", data.title, data.line))
+        .with_text_color(theme::PRIMARY_LIGHT)
+        .padding(druid::Insets::new(5.0, 2.0, 5.0, 2.0))
+        .on_click(|ctx, data: &mut TodoItem, _| {
+            ctx.submit_command(Command::new(
+                OPEN_AT,
+                (data.path.clone(), data.line),
+                Target::Global,
+            ))
+        });
+
+    Flex::row()
+        .with_child(checkbox)
+        .with_flex_child(
+            Label::new(|data: &TodoItem, _: &Env| data.text.to_string()).expand_width(),
+            1.0,
+        )
+        .with_child(source)
+}
+
+/// Every open task in the notes matching the search
+pub(crate) fn todos_list() -> impl Widget<FragmentState> {
+    Scroll::new(List::new(|| todo_row()).lens(FragmentState::todos))
+        .vertical()
+        .expand_width()
+}
+
+/// Switches the results pane between notes, open tasks and recent notes
+pub(crate) fn view_switcher() -> impl Widget<FragmentState> {
+    let tab = |label: fn(&FragmentState) -> String, view: ResultsView| {
+        Label::new(move |data: &FragmentState, _: &Env| label(data))
+            .padding(druid::Insets::new(8.0, 3.0, 8.0, 3.0))
+            .env_scope(move |env, data: &FragmentState| {
+                if data.view != view {
+                    env.set(theme::LABEL_COLOR, Color::rgb8(150, 150, 150));
+                }
+            })
+            .on_click(move |_, data: &mut FragmentState, _| data.view = view)
+    };
+
+    Flex::row()
+        .with_child(tab(
+            |data| format!("Notes ({})", data.results.len()),
+            ResultsView::Notes,
+        ))
+        .with_child(tab(
+            |data| format!("Todos ({})", data.todos.len()),
+            ResultsView::Todos,
+        ))
+        .with_child(tab(
+            |data| format!("Recent ({})", data.recent_notes.len()),
+            ResultsView::Recent,
+        ))
+        .expand_width()
+}
+
+impl FragmentState {
+    /// Ticks off an open task in its note
+    pub(crate) fn toggle_task(&mut self, todo: &TodoItem) {
+        let task = Task {
+            line: todo.line,
+            text: todo.text.clone(),
+            done: false,
+        };
+        self.status = match tasks::toggle(Path::new(todo.path.as_ref()), &task) {
+            Ok(()) => format!("Done: {}", todo.text),
+            Err(err) => format!("Couldn't update {}: {}", todo.title, err),
+        };
+    }
+}
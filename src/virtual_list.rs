@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+use std::sync::Arc;
+
+use druid::widget::prelude::*;
+use druid::{theme, Command, Point, Rect, Selector, WidgetPod};
+
+/// Sent to the list itself when it's been resized, to build the rows that
+/// came into view. Rows can't be added in the middle of layout.
+const SYNC_ROWS: Selector = Selector::new("fragment.virtual-list.sync-rows");
+
+/// Rows built beyond each edge of the view, so a little scrolling doesn't
+/// have to build any
+const OVERSCAN: usize = 3;
+
+/// A scrolling list that only builds, lays out and paints the rows in view,
+/// for folders with tens of thousands of notes.
+///
+/// Rows all have the same height, so which are in view is known without
+/// laying any out. Each row is keyed, and keeps its widget for as long as
+/// its key stays in view, however the list around it changes.
+pub struct VirtualList<S, T, K, W> {
+    make_row: Box<dyn Fn() -> W>,
+    key: fn(&T) -> K,
+    row_height: f64,
+    /// The rows in view, by key
+    rows: HashMap<K, WidgetPod<(S, T), W>>,
+    /// Where each row in view is in the list
+    visible: Vec<(usize, K)>,
+    /// How far down the list is scrolled
+    offset: f64,
+    size: Size,
+    /// Where a scrollbar drag started, and the offset then
+    drag: Option<(f64, f64)>,
+}
+
+impl<S: Data, T: Data, K: Clone + Eq + Hash, W: Widget<(S, T)>> VirtualList<S, T, K, W> {
+    pub fn new(make_row: impl Fn() -> W + 'static, key: fn(&T) -> K, row_height: f64) -> Self {
+        VirtualList {
+            make_row: Box::new(make_row),
+            key,
+            row_height,
+            rows: HashMap::new(),
+            visible: Vec::new(),
+            offset: 0.0,
+            size: Size::ZERO,
+            drag: None,
+        }
+    }
+
+    fn content_height(&self, data: &(S, Arc<Vec<T>>)) -> f64 {
+        data.1.len() as f64 * self.row_height
+    }
+
+    fn max_offset(&self, data: &(S, Arc<Vec<T>>)) -> f64 {
+        (self.content_height(data) - self.size.height).max(0.0)
+    }
+
+    fn thumb(&self, data: &(S, Arc<Vec<T>>)) -> Option<(f64, f64)> {
+        thumb(self.offset, self.content_height(data), self.size.height)
+    }
+
+    /// Works out which rows are in view, dropping the widgets of rows that
+    /// have gone. Returns the keys of rows that need building.
+    fn update_visible(&mut self, data: &(S, Arc<Vec<T>>)) -> Vec<(usize, K)> {
+        self.offset = self.offset.min(self.max_offset(data)).max(0.0);
+        let range = visible_range(self.offset, self.row_height, self.size.height, data.1.len());
+
+        let key = self.key;
+        self.visible = range.map(|i| (i, key(&data.1[i]))).collect();
+        let visible = &self.visible;
+        self.rows
+            .retain(|row, _| visible.iter().any(|(_, key)| key == row));
+        self.visible
+            .iter()
+            .filter(|(_, key)| !self.rows.contains_key(key))
+            .cloned()
+            .collect()
+    }
+
+    /// Builds the rows that came into view. Returns whether there were any.
+    fn build_rows(&mut self, missing: Vec<(usize, K)>) -> bool {
+        let built = !missing.is_empty();
+        for (_, key) in missing {
+            self.rows.insert(key, WidgetPod::new((self.make_row)()));
+        }
+        built
+    }
+
+    fn scroll_to(&mut self, offset: f64, data: &(S, Arc<Vec<T>>)) -> bool {
+        let before = self.offset;
+        self.offset = offset;
+        let missing = self.update_visible(data);
+        self.build_rows(missing);
+        self.offset != before
+    }
+}
+
+/// The rows to build with the list scrolled `offset` down a view `height`
+/// tall: those in view, and `OVERSCAN` more beyond each edge
+fn visible_range(offset: f64, row_height: f64, height: f64, len: usize) -> Range<usize> {
+    let first = ((offset / row_height) as usize).saturating_sub(OVERSCAN);
+    let shown = (height / row_height).ceil() as usize + 2 * OVERSCAN + 1;
+    first.min(len)..(first + shown).min(len)
+}
+
+/// The scrollbar thumb's height and how far down it is, or `None` when the
+/// `content` all fits in the view
+fn thumb(offset: f64, content: f64, height: f64) -> Option<(f64, f64)> {
+    if content <= height {
+        return None;
+    }
+    let thumb = (height * height / content).max(20.0);
+    let top = offset / (content - height) * (height - thumb);
+    Some((thumb, top))
+}
+
+impl<S: Data, T: Data, K: Clone + Eq + Hash, W: Widget<(S, T)>> Widget<(S, Arc<Vec<T>>)>
+    for VirtualList<S, T, K, W>
+{
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut (S, Arc<Vec<T>>), env: &Env) {
+        match event {
+            Event::Command(cmd) if cmd.is(SYNC_ROWS) => {
+                let missing = self.update_visible(data);
+                if self.build_rows(missing) {
+                    ctx.children_changed();
+                }
+                ctx.request_layout();
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                let bar_width = env.get(theme::SCROLLBAR_WIDTH) + env.get(theme::SCROLLBAR_PAD);
+                if self.thumb(data).is_some() && mouse.pos.x > self.size.width - bar_width {
+                    self.drag = Some((mouse.pos.y, self.offset));
+                    ctx.set_active(true);
+                    ctx.set_handled();
+                    return;
+                }
+            }
+            Event::MouseMove(mouse) if self.drag.is_some() => {
+                if let (Some((start_y, start_offset)), Some((thumb, _))) =
+                    (self.drag, self.thumb(data))
+                {
+                    let per_pixel = self.max_offset(data) / (self.size.height - thumb);
+                    let offset = start_offset + (mouse.pos.y - start_y) * per_pixel;
+                    if self.scroll_to(offset, data) {
+                        ctx.children_changed();
+                        ctx.request_layout();
+                    }
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseUp(_) if self.drag.is_some() => {
+                self.drag = None;
+                ctx.set_active(false);
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+
+        for (i, key) in &self.visible {
+            if let Some(row) = self.rows.get_mut(key) {
+                let mut row_data = (data.0.clone(), data.1[*i].clone());
+                row.event(ctx, event, &mut row_data, env);
+                if !row_data.0.same(&data.0) {
+                    data.0 = row_data.0;
+                }
+                if !row_data.1.same(&data.1[*i]) {
+                    Arc::make_mut(&mut data.1)[*i] = row_data.1;
+                }
+            }
+        }
+
+        if let Event::Wheel(wheel) = event {
+            if !ctx.is_handled() {
+                if self.scroll_to(self.offset + wheel.wheel_delta.y, data) {
+                    ctx.children_changed();
+                    ctx.request_layout();
+                }
+                ctx.set_handled();
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &(S, Arc<Vec<T>>),
+        env: &Env,
+    ) {
+        for (i, key) in &self.visible {
+            if let Some(row) = self.rows.get_mut(key) {
+                row.lifecycle(ctx, event, &(data.0.clone(), data.1[*i].clone()), env);
+            }
+        }
+        if let LifeCycle::Size(size) = event {
+            self.size = *size;
+            ctx.submit_command(Command::from(SYNC_ROWS).to(ctx.widget_id()));
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &(S, Arc<Vec<T>>),
+        data: &(S, Arc<Vec<T>>),
+        env: &Env,
+    ) {
+        if old_data.same(data) {
+            return;
+        }
+        // Rows still in view keep their widgets, wherever they've moved to
+        let missing = self.update_visible(data);
+        for (i, key) in &self.visible {
+            if let Some(row) = self.rows.get_mut(key) {
+                row.update(ctx, &(data.0.clone(), data.1[*i].clone()), env);
+            }
+        }
+        if self.build_rows(missing) {
+            ctx.children_changed();
+        }
+        if !old_data.1.same(&data.1) {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &(S, Arc<Vec<T>>),
+        env: &Env,
+    ) -> Size {
+        let width = bc.max().width;
+        let height = if bc.is_height_bounded() {
+            bc.max().height
+        } else {
+            self.content_height(data)
+        };
+        let row_bc = BoxConstraints::tight(Size::new(width, self.row_height));
+        for (i, key) in &self.visible {
+            if let Some(row) = self.rows.get_mut(key) {
+                let row_data = (data.0.clone(), data.1[*i].clone());
+                row.layout(ctx, &row_bc, &row_data, env);
+                let origin = Point::new(0.0, *i as f64 * self.row_height - self.offset);
+                let rect = Rect::from_origin_size(origin, Size::new(width, self.row_height));
+                row.set_layout_rect(ctx, &row_data, env, rect);
+            }
+        }
+        bc.constrain(Size::new(width, height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &(S, Arc<Vec<T>>), env: &Env) {
+        let bounds = ctx.size().to_rect();
+        let rows = &mut self.rows;
+        let visible = &self.visible;
+        ctx.with_save(|ctx| {
+            ctx.clip(bounds);
+            for (i, key) in visible {
+                if let Some(row) = rows.get_mut(key) {
+                    row.paint(ctx, &(data.0.clone(), data.1[*i].clone()), env);
+                }
+            }
+        });
+
+        if let Some((height, top)) = self.thumb(data) {
+            let width = env.get(theme::SCROLLBAR_WIDTH);
+            let pad = env.get(theme::SCROLLBAR_PAD);
+            let thumb = Rect::from_origin_size(
+                Point::new(bounds.width() - width - pad, top),
+                Size::new(width, height),
+            )
+            .to_rounded_rect(env.get(theme::SCROLLBAR_RADIUS));
+            ctx.fill(thumb, &env.get(theme::SCROLLBAR_COLOR));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use druid::widget::SizedBox;
+
+    use super::*;
+
+    #[test]
+    fn only_rows_in_view_and_the_overscan_are_built() {
+        assert_eq!(visible_range(0.0, 20.0, 100.0, 10_000), 0..12);
+        // Scrolled half a row, the row partly in view at the bottom counts
+        assert_eq!(visible_range(2010.0, 20.0, 100.0, 10_000), 97..109);
+        assert_eq!(visible_range(0.0, 20.0, 100.0, 4), 0..4);
+        assert_eq!(visible_range(0.0, 20.0, 100.0, 0), 0..0);
+    }
+
+    #[test]
+    fn the_thumb_only_shows_when_the_rows_overflow() {
+        assert_eq!(thumb(0.0, 100.0, 100.0), None);
+        assert_eq!(thumb(0.0, 400.0, 100.0), Some((25.0, 0.0)));
+        assert_eq!(thumb(300.0, 400.0, 100.0), Some((25.0, 75.0)));
+        // Long lists still get a thumb big enough to grab
+        assert_eq!(thumb(0.0, 1_000_000.0, 100.0), Some((20.0, 0.0)));
+    }
+
+    #[test]
+    fn rows_keep_their_widgets_while_their_key_is_in_view() {
+        let mut list: VirtualList<(), u32, u32, SizedBox<((), u32)>> =
+            VirtualList::new(SizedBox::empty, |row| *row, 20.0);
+        list.size = Size::new(100.0, 100.0);
+        let data = ((), Arc::new((0..100).collect::<Vec<u32>>()));
+
+        let missing = list.update_visible(&data);
+        assert_eq!(missing.len(), 12);
+        list.build_rows(missing);
+        assert!(list.update_visible(&data).is_empty());
+
+        // A row inserted at the top shifts the rest down without rebuilding them
+        let shifted = ((), Arc::new((100..101).chain(0..100).collect::<Vec<u32>>()));
+        assert_eq!(list.update_visible(&shifted), vec![(0, 100)]);
+        assert_eq!(list.visible[1], (1, 0));
+        assert_eq!(list.rows.len(), 11);
+
+        // Scrolling past the end stops at the last row
+        assert!(list.scroll_to(10_000.0, &data));
+        assert_eq!(list.offset, 1900.0);
+        assert_eq!(list.visible.last(), Some(&(99, 99)));
+    }
+}
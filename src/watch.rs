@@ -1,25 +1,156 @@
-use druid::{ExtEventSink, Target};
-use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
-pub fn watch(path: String, event_sink: ExtEventSink) -> RecommendedWatcher {
+use druid::{Data, ExtEventSink, Target};
+use fragment_core::config::{Config, WatchMode};
+use fragment_core::index::Index;
+use fragment_core::watch::{self, Change};
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::delegate::{APPLY_CHANGES, LIVE_UPDATES, START_SEARCH};
+
+/// Whether changes to the notes show up without a manual refresh
+#[derive(Clone, Data, Debug, PartialEq)]
+pub enum LiveUpdates {
+    /// Not started yet
+    Starting,
+    /// The OS is telling us about changes
+    Watching,
+    /// Scanning for changes every so often, with why if it wasn't asked for
+    Polling(Option<Arc<str>>),
+    /// No updates until the watcher can be set up again, with why
+    Down(Arc<str>),
+}
+
+/// Watches the notes folder in the background, sending each burst of changes
+/// to the app to apply once it's gone quiet, after dropping the notes it
+/// touched from `index`. A watcher that breaks is set up again every
+/// `poll_interval`, and the OS watcher falls back to polling when it can't
+/// be had.
+pub fn watch(path: String, config: &Config, index: Arc<Mutex<Index>>, event_sink: ExtEventSink) {
+    let (sender, receiver) = mpsc::channel();
+
+    let root = path.clone();
+    let sink = event_sink.clone();
+    thread::spawn(move || {
+        watch::debounce(
+            &receiver,
+            &root,
+            watch::QUIET_PERIOD,
+            watch::MAX_WAIT,
+            |batch| {
+                index.lock().unwrap().forget(&batch);
+                let _ = sink.submit_command(APPLY_CHANGES, batch, Target::Global);
+            },
+        )
+    });
+
+    let mode = config.watcher;
+    let interval = config.poll_interval;
+    thread::spawn(move || supervise(&path, mode, interval, &sender, &event_sink));
+}
+
+/// Keeps a watcher running for as long as the app is
+fn supervise(
+    root: &str,
+    mode: WatchMode,
+    interval: Duration,
+    changes: &Sender<Change>,
+    event_sink: &ExtEventSink,
+) {
+    let mut missed_changes = false;
+    loop {
+        if !Path::new(root).is_dir() {
+            report(
+                event_sink,
+                LiveUpdates::Down("the notes folder is missing".into()),
+            );
+            missed_changes = true;
+            thread::sleep(interval);
+            continue;
+        }
+        if missed_changes {
+            // Whatever happened while nothing was watching
+            let _ = event_sink.submit_command(START_SEARCH, (), Target::Global);
+            missed_changes = false;
+        }
+
+        let trouble = match mode {
+            WatchMode::Native => match watch_natively(root, interval, changes, event_sink) {
+                Ok(trouble) => trouble,
+                Err(err) => {
+                    let reason = format!("couldn't watch for changes: {}", err);
+                    report(event_sink, LiveUpdates::Polling(Some(reason.into())));
+                    watch::poll(root, interval, changes);
+                    "the notes folder went missing".to_string()
+                }
+            },
+            WatchMode::Poll => {
+                report(event_sink, LiveUpdates::Polling(None));
+                watch::poll(root, interval, changes);
+                "the notes folder went missing".to_string()
+            }
+        };
+        eprintln!("watch error: {}", trouble);
+        report(event_sink, LiveUpdates::Down(trouble.into()));
+        missed_changes = true;
+        thread::sleep(interval);
+    }
+}
+
+/// Watches `root` with the OS's file events until something goes wrong,
+/// returning what did. Errors setting up the watcher come back as `Err`.
+fn watch_natively(
+    root: &str,
+    interval: Duration,
+    changes: &Sender<Change>,
+    event_sink: &ExtEventSink,
+) -> Result<String, notify::Error> {
+    let (trouble_sender, trouble) = mpsc::channel();
+    let canonical_root = Path::new(root).canonicalize()?;
+
+    let sender = changes.clone();
+    let watched_root = canonical_root.clone();
     let mut watcher: RecommendedWatcher = Watcher::new_immediate(move |res| match res {
         Ok(event) => {
-            println!("event: {:?}", event);
-            if let Err(_) =
-                event_sink.submit_command(super::delegate::START_REFRESH, (), Target::Global)
-            {
+            let root_removed = match event.kind {
+                EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_)) => {
+                    event.paths.iter().any(|path| path == &watched_root)
+                }
+                _ => false,
+            };
+            if root_removed {
+                let _ = trouble_sender.send("the notes folder was moved or removed".into());
             }
+            let _ = sender.send(Change { paths: event.paths });
         }
-        Err(e) => println!("watch error: {:?}", e),
-    })
-    .expect("Couldn't create watcher");
-
-    watcher
-        .watch(
-            std::path::Path::new(&path.clone()),
-            RecursiveMode::Recursive,
-        )
-        .expect("Watcher couldn't watch");
+        Err(err) => {
+            let _ = trouble_sender.send(err.to_string());
+        }
+    })?;
+    watcher.watch(&canonical_root, RecursiveMode::Recursive)?;
+    report(event_sink, LiveUpdates::Watching);
+
+    loop {
+        match trouble.recv_timeout(interval) {
+            Ok(trouble) => return Ok(trouble),
+            Err(RecvTimeoutError::Timeout) => {
+                // Some platforms don't say when the watched folder goes away
+                if canonical_root.canonicalize().ok().as_ref() != Some(&canonical_root) {
+                    return Ok("the notes folder was moved or removed".to_string());
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Ok("the watcher stopped".to_string());
+            }
+        }
+    }
+}
 
-    watcher
+fn report(event_sink: &ExtEventSink, status: LiveUpdates) {
+    let _ = event_sink.submit_command(LIVE_UPDATES, status, Target::Global);
 }